@@ -0,0 +1,92 @@
+//! A toy 8-bit CPU built from gates, running a program that multiplies 6 by 7 by repeated
+//! addition and doubles the product with a shift.
+//!
+//! Instructions are 16 bits: a 4-bit opcode, a destination register `rd`, a source register `rs`
+//! and an 8-bit immediate, from the most significant bit down.
+//!
+//! | opcode | instruction                             |
+//! |--------|-----------------------------------------|
+//! | 0..=6  | `rd = rd op rs`, with `AluOp::ALL[op]`  |
+//! | 7      | `rd = 0`                                |
+//! | 8      | `rd = imm`                              |
+//! | 9      | jump to `imm` if `rd` is not zero       |
+
+use emulator::cpu::AluOp;
+use emulator::emulator::Emulator;
+use emulator::netlist::Netlist;
+
+const LOAD: u16 = 8;
+const JUMP_IF_NOT_ZERO: u16 = 9;
+
+fn encode(opcode: u16, rd: u16, rs: u16, imm: u16) -> u64 {
+    (opcode << 12 | rd << 10 | rs << 8 | imm).into()
+}
+
+fn alu_op(op: AluOp, rd: u16, rs: u16) -> u64 {
+    let opcode = AluOp::ALL.iter().position(|other| *other == op).unwrap();
+    encode(opcode as u16, rd, rs, 0)
+}
+
+fn cpu() -> Emulator {
+    let mut netlist = Netlist::new();
+    let instruction = netlist.input_bus(0, 16);
+    let imm = instruction.slice(..8);
+    let rs = instruction.slice(8..10);
+    let rd = instruction.slice(10..12);
+    let opcode = instruction.slice(12..);
+
+    let registers = (0..4).map(|_| netlist.state_bus(8)).collect::<Vec<_>>();
+    let pc = netlist.state_bus(8);
+    let a = netlist.read_register(&registers, &rd);
+    let b = netlist.read_register(&registers, &rs);
+
+    let alu = netlist.alu(&AluOp::ALL, &opcode.slice(..3), &a, &b);
+    let is_alu = netlist.not(opcode.bit(3));
+    let load_code = netlist.constant_bus(LOAD.into(), 4);
+    let is_load = netlist.equal(&opcode, &load_code);
+    let jump_code = netlist.constant_bus(JUMP_IF_NOT_ZERO.into(), 4);
+    let is_jump = netlist.equal(&opcode, &jump_code);
+
+    let data = netlist.bus_mux(&[is_load], &[alu.result, imm.clone()]);
+    let write = netlist.or([is_alu, is_load]);
+    netlist.register_file(&registers, &rd, &data, write);
+
+    let zero = netlist.constant_bus(0, 8);
+    let a_is_zero = netlist.equal(&a, &zero);
+    let a_is_nonzero = netlist.not(a_is_zero);
+    let jump = netlist.and([is_jump, a_is_nonzero]);
+    netlist.program_counter(&pc, jump, &imm);
+
+    let mut outputs = vec![("pc", pc)];
+    outputs.extend(["r0", "r1", "r2", "r3"].into_iter().zip(registers));
+    Emulator::from_buses([("instruction", instruction)], netlist, outputs).unwrap()
+}
+
+fn main() {
+    let program = [
+        encode(LOAD, 0, 0, 6),
+        encode(LOAD, 1, 0, 7),
+        encode(LOAD, 3, 0, 1),
+        alu_op(AluOp::Add, 2, 1),
+        alu_op(AluOp::Sub, 0, 3),
+        encode(JUMP_IF_NOT_ZERO, 0, 0, 3),
+        alu_op(AluOp::ShiftLeft, 2, 3),
+        encode(JUMP_IF_NOT_ZERO, 3, 0, 7),
+    ];
+    let mut cpu = cpu();
+    println!("cycle  pc  r0  r1  r2  r3");
+    for cycle in 0..30 {
+        // The program counter only depends on the state, so any instruction reads it.
+        let pc = cpu.emulate_buses(&[0]).unwrap()[0];
+        let outputs = cpu.step_buses(&[program[pc as usize]]).unwrap();
+        let [pc, r0, r1, r2, r3] = outputs[..] else {
+            unreachable!()
+        };
+        println!("{cycle:5} {pc:3} {r0:3} {r1:3} {r2:3} {r3:3}");
+    }
+    let [_, _, _, r2, _] = cpu.emulate_buses(&[0]).unwrap()[..] else {
+        unreachable!()
+    };
+    assert_eq!(r2, 6 * 7 * 2);
+    println!("6 * 7 * 2 = {r2}");
+}
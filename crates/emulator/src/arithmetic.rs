@@ -0,0 +1,148 @@
+use crate::bus::{assert_bus_widths, Bus};
+use crate::netlist::{Netlist, NodeId};
+
+/// Unsigned arithmetic on buses. Adders return the sum, as wide as the operands, and the carry out.
+impl Netlist {
+    /// Returns the sum and carry of two bits.
+    pub fn half_adder(&mut self, a: NodeId, b: NodeId) -> (NodeId, NodeId) {
+        (self.parity([a, b]), self.and([a, b]))
+    }
+
+    /// Returns the sum and carry of three bits.
+    pub fn full_adder(&mut self, a: NodeId, b: NodeId, carry: NodeId) -> (NodeId, NodeId) {
+        (self.parity([a, b, carry]), self.majority([a, b, carry]))
+    }
+
+    /// Adds with a chain of full adders, one gate delay per bit for the carry.
+    pub fn ripple_carry_adder(&mut self, a: &Bus, b: &Bus, carry: NodeId) -> (Bus, NodeId) {
+        assert_bus_widths(a, b);
+        let mut carry = carry;
+        let sum = Bus::new(a.bits().iter().zip(b.bits()).map(|(a, b)| {
+            let (sum, carry_out) = self.full_adder(*a, *b, carry);
+            carry = carry_out;
+            sum
+        }));
+        (sum, carry)
+    }
+
+    /// Adds with every carry computed directly from the generate and propagate signals of the bits
+    /// below it, in two levels of logic.
+    pub fn carry_lookahead_adder(&mut self, a: &Bus, b: &Bus, carry: NodeId) -> (Bus, NodeId) {
+        assert_bus_widths(a, b);
+        let (generate, propagate) = self.generate_propagate(a, b);
+        let mut carries = vec![carry];
+        for bit in 0..a.width() {
+            // Carry into `bit + 1` is generated at some bit `j` and propagated by every bit above.
+            let mut terms = vec![generate[bit]];
+            for j in (0..bit).rev() {
+                terms.push(self.and(propagate[j + 1..=bit].iter().copied().chain([generate[j]])));
+            }
+            terms.push(self.and(propagate[..=bit].iter().copied().chain([carry])));
+            carries.push(self.or(terms));
+        }
+        self.sum_bits(&propagate, carries)
+    }
+
+    /// Adds with a Kogge–Stone parallel prefix network, computing every carry in a number of
+    /// levels logarithmic in the width.
+    pub fn kogge_stone_adder(&mut self, a: &Bus, b: &Bus, carry: NodeId) -> (Bus, NodeId) {
+        assert_bus_widths(a, b);
+        let (generate, propagate) = self.generate_propagate(a, b);
+        // The carry in is treated as a bit below bit 0 that generates but never propagates.
+        let zero = self.constant(false);
+        let mut groups = [(carry, zero)]
+            .into_iter()
+            .chain(generate.iter().copied().zip(propagate.iter().copied()))
+            .collect::<Vec<_>>();
+        let mut distance = 1;
+        while distance < groups.len() {
+            let previous = groups.clone();
+            for bit in distance..groups.len() {
+                let (high_generate, high_propagate) = previous[bit];
+                let (low_generate, low_propagate) = previous[bit - distance];
+                let carried = self.and([high_propagate, low_generate]);
+                groups[bit] = (
+                    self.or([high_generate, carried]),
+                    self.and([high_propagate, low_propagate]),
+                );
+            }
+            distance *= 2;
+        }
+        let carries = groups.into_iter().map(|(generate, _)| generate).collect();
+        self.sum_bits(&propagate, carries)
+    }
+
+    /// Returns `a - b` and the borrow out, which is set if `b` is greater than `a`.
+    pub fn subtractor(&mut self, a: &Bus, b: &Bus) -> (Bus, NodeId) {
+        let not_b = self.bus_not(b);
+        let one = self.constant(true);
+        let (difference, carry) = self.ripple_carry_adder(a, &not_b, one);
+        (difference, self.not(carry))
+    }
+
+    /// Returns `a + 1` and the carry out, with a chain of half adders.
+    pub fn incrementer(&mut self, a: &Bus) -> (Bus, NodeId) {
+        let mut carry = self.constant(true);
+        let sum = Bus::new(a.bits().iter().map(|bit| {
+            let (sum, carry_out) = self.half_adder(*bit, carry);
+            carry = carry_out;
+            sum
+        }));
+        (sum, carry)
+    }
+
+    pub fn equal(&mut self, a: &Bus, b: &Bus) -> NodeId {
+        let differences = self.bus_xor(a, b);
+        let different = self.any(differences.bits());
+        self.not(different)
+    }
+
+    /// Compares `a` and `b` as unsigned numbers.
+    pub fn less_than(&mut self, a: &Bus, b: &Bus) -> NodeId {
+        self.subtractor(a, b).1
+    }
+
+    /// Compares `a` and `b` as unsigned numbers.
+    pub fn greater_than(&mut self, a: &Bus, b: &Bus) -> NodeId {
+        self.less_than(b, a)
+    }
+
+    /// Multiplies with an array of ripple-carry adders, one row per bit of `b`.
+    ///
+    /// The product is as wide as `a` and `b` together, so it never overflows.
+    pub fn array_multiplier(&mut self, a: &Bus, b: &Bus) -> Bus {
+        if a.width() == 0 || b.width() == 0 {
+            return self.constant_bus(0, a.width() + b.width());
+        }
+        let zero = self.constant(false);
+        let mut product = Vec::new();
+        // The partial sum of the rows so far, shifted right by the bits already in `product`.
+        let mut sum = Bus::from(zero).replicate(a.width());
+        for bit in b.bits() {
+            let partial = self.bus_and(a, &Bus::from(*bit).replicate(a.width()));
+            let (row, carry) = self.ripple_carry_adder(&sum, &partial, zero);
+            product.push(row.bit(0));
+            sum = Bus::concat([Bus::from(carry), row.slice(1..)]);
+        }
+        product.extend(sum.bits());
+        Bus::new(product)
+    }
+
+    fn generate_propagate(&mut self, a: &Bus, b: &Bus) -> (Vec<NodeId>, Vec<NodeId>) {
+        let generate = self.bus_and(a, b).bits().to_vec();
+        let propagate = self.bus_xor(a, b).bits().to_vec();
+        (generate, propagate)
+    }
+
+    /// Combines the propagate signals with the carries into every bit and out of the top bit.
+    fn sum_bits(&mut self, propagate: &[NodeId], mut carries: Vec<NodeId>) -> (Bus, NodeId) {
+        let carry = carries.pop().unwrap();
+        let sum = Bus::new(
+            propagate
+                .iter()
+                .zip(carries)
+                .map(|(propagate, carry)| self.parity([*propagate, carry])),
+        );
+        (sum, carry)
+    }
+}
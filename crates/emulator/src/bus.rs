@@ -0,0 +1,131 @@
+use std::ops::RangeBounds;
+
+use crate::netlist::{Netlist, NodeId};
+
+/// A group of signals forming a word, least significant bit first.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bus {
+    bits: Vec<NodeId>,
+}
+
+impl Bus {
+    /// Creates a bus from its bits, least significant first.
+    pub fn new(bits: impl IntoIterator<Item = NodeId>) -> Self {
+        Self {
+            bits: bits.into_iter().collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn bits(&self) -> &[NodeId] {
+        &self.bits
+    }
+
+    pub fn bit(&self, index: usize) -> NodeId {
+        self.bits[index]
+    }
+
+    /// Returns the bits in `range`, counted from the least significant bit.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Bus {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        Bus::new(self.bits[range].iter().copied())
+    }
+
+    /// Joins buses like Verilog's `{a, b}`: the first part ends up in the most significant bits.
+    pub fn concat(parts: impl IntoIterator<Item = Bus>) -> Bus {
+        let parts = parts.into_iter().collect::<Vec<_>>();
+        Bus::new(
+            parts
+                .iter()
+                .rev()
+                .flat_map(|part| part.bits.iter().copied()),
+        )
+    }
+
+    /// Repeats the bus `count` times, like Verilog's `{count{a}}`.
+    pub fn replicate(&self, count: usize) -> Bus {
+        Bus::new((0..count).flat_map(|_| self.bits.iter().copied()))
+    }
+}
+
+impl From<NodeId> for Bus {
+    fn from(bit: NodeId) -> Self {
+        Bus { bits: vec![bit] }
+    }
+}
+
+impl Netlist {
+    /// Adds a bus of `width` inputs starting at input `first`, which is the most significant bit,
+    /// so that truth-table rows read as numbers.
+    pub fn input_bus(&mut self, first: usize, width: usize) -> Bus {
+        Bus::new((0..width).rev().map(|offset| self.input(first + offset)))
+    }
+
+    /// Adds a bus of `width` state bits.
+    pub fn state_bus(&mut self, width: usize) -> Bus {
+        Bus::new((0..width).map(|_| self.state()))
+    }
+
+    /// Sets the value every state bit of `state` takes on the next clock edge.
+    pub fn set_next_bus(&mut self, state: &Bus, next: &Bus) {
+        assert_bus_widths(state, next);
+        for (state, next) in state.bits.iter().zip(&next.bits) {
+            self.set_next(*state, *next);
+        }
+    }
+
+    pub fn constant_bus(&mut self, value: u64, width: usize) -> Bus {
+        Bus::new((0..width).map(|bit| self.constant(bit < 64 && value >> bit & 1 != 0)))
+    }
+
+    pub fn bus_not(&mut self, bus: &Bus) -> Bus {
+        Bus::new(bus.bits.iter().map(|bit| self.not(*bit)))
+    }
+
+    pub fn bus_and(&mut self, a: &Bus, b: &Bus) -> Bus {
+        assert_bus_widths(a, b);
+        Bus::new(a.bits.iter().zip(&b.bits).map(|(a, b)| self.and([*a, *b])))
+    }
+
+    pub fn bus_or(&mut self, a: &Bus, b: &Bus) -> Bus {
+        assert_bus_widths(a, b);
+        Bus::new(a.bits.iter().zip(&b.bits).map(|(a, b)| self.or([*a, *b])))
+    }
+
+    pub fn bus_xor(&mut self, a: &Bus, b: &Bus) -> Bus {
+        assert_bus_widths(a, b);
+        Bus::new(
+            a.bits
+                .iter()
+                .zip(&b.bits)
+                .map(|(a, b)| self.parity([*a, *b])),
+        )
+    }
+
+    /// Selects `inputs[select]` bit by bit; there must be one bus per value of `select`.
+    pub fn bus_mux(&mut self, select: &[NodeId], inputs: &[Bus]) -> Bus {
+        for input in inputs {
+            assert_bus_widths(&inputs[0], input);
+        }
+        let width = inputs.first().map_or(0, Bus::width);
+        Bus::new((0..width).map(|bit| {
+            let bits = inputs
+                .iter()
+                .map(|input| input.bits[bit])
+                .collect::<Vec<_>>();
+            self.mux(select, &bits)
+        }))
+    }
+}
+
+pub(crate) fn assert_bus_widths(a: &Bus, b: &Bus) {
+    assert_eq!(a.width(), b.width(), "Buses must have the same width");
+}
+
+/// Returns the bits of `bus` most significant first, as multiplexers and decoders expect.
+pub(crate) fn select_lines(bus: &Bus) -> Vec<NodeId> {
+    bus.bits().iter().rev().copied().collect()
+}
@@ -0,0 +1,165 @@
+use crate::bus::{assert_bus_widths, select_lines, Bus};
+use crate::netlist::{Netlist, NodeId};
+
+/// An operation of an [`alu`](Netlist::alu).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AluOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    /// Shifts `a` left by `b` modulo the next power of two of the width, shifting in zeros.
+    ShiftLeft,
+    /// Shifts `a` right like [`ShiftLeft`](Self::ShiftLeft).
+    ShiftRight,
+}
+
+impl AluOp {
+    pub const ALL: [AluOp; 7] = [
+        AluOp::Add,
+        AluOp::Sub,
+        AluOp::And,
+        AluOp::Or,
+        AluOp::Xor,
+        AluOp::ShiftLeft,
+        AluOp::ShiftRight,
+    ];
+}
+
+/// The outputs of an [`alu`](Netlist::alu).
+#[derive(Clone, Debug)]
+pub struct Alu {
+    pub result: Bus,
+    /// Set if every bit of the result is zero.
+    pub zero: NodeId,
+    /// The most significant bit of the result.
+    pub negative: NodeId,
+    /// The carry out of an addition or the borrow of a subtraction; clear for other operations.
+    pub carry: NodeId,
+    /// Set if an addition or subtraction overflows as a two's complement number.
+    pub overflow: NodeId,
+}
+
+impl Netlist {
+    /// Adds an ALU performing `operations[op]` on `a` and `b`.
+    ///
+    /// `op` must be wide enough to select every operation; codes beyond the last operation give a
+    /// zero result with every flag but `zero` clear.
+    pub fn alu(&mut self, operations: &[AluOp], op: &Bus, a: &Bus, b: &Bus) -> Alu {
+        assert_bus_widths(a, b);
+        assert!(
+            op.width() > 0 && operations.len() <= 1 << op.width(),
+            "ALU requires an opcode wide enough for its operations"
+        );
+        let width = a.width();
+        let zero = self.constant(false);
+        let mut results = Vec::new();
+        let mut carries = Vec::new();
+        let mut overflows = Vec::new();
+        for operation in operations {
+            let (result, carry, overflow) = match operation {
+                AluOp::Add => {
+                    let (sum, carry) = self.ripple_carry_adder(a, b, zero);
+                    let overflow = self.signed_overflow(a, b, &sum, false);
+                    (sum, carry, overflow)
+                }
+                AluOp::Sub => {
+                    let (difference, borrow) = self.subtractor(a, b);
+                    let overflow = self.signed_overflow(a, b, &difference, true);
+                    (difference, borrow, overflow)
+                }
+                AluOp::And => (self.bus_and(a, b), zero, zero),
+                AluOp::Or => (self.bus_or(a, b), zero, zero),
+                AluOp::Xor => (self.bus_xor(a, b), zero, zero),
+                AluOp::ShiftLeft => (self.shifter(a, b, true), zero, zero),
+                AluOp::ShiftRight => (self.shifter(a, b, false), zero, zero),
+            };
+            results.push(result);
+            carries.push(carry);
+            overflows.push(overflow);
+        }
+        let codes = 1 << op.width();
+        results.resize(codes, Bus::from(zero).replicate(width));
+        carries.resize(codes, zero);
+        overflows.resize(codes, zero);
+        let select = select_lines(op);
+        let result = self.bus_mux(&select, &results);
+        let nonzero = self.any(result.bits());
+        Alu {
+            zero: self.not(nonzero),
+            negative: result.bits().last().copied().unwrap_or(zero),
+            carry: self.mux(&select, &carries),
+            overflow: self.mux(&select, &overflows),
+            result,
+        }
+    }
+
+    /// Makes `registers` a register file: on every clock edge with `enable` high, the register
+    /// selected by `address` loads `data`.
+    pub fn register_file(&mut self, registers: &[Bus], address: &Bus, data: &Bus, enable: NodeId) {
+        assert_eq!(
+            registers.len(),
+            1 << address.width(),
+            "Register file requires one register per address"
+        );
+        let reset = self.constant(false);
+        let selected = self.decoder(&select_lines(address));
+        for (register, selected) in registers.iter().zip(selected) {
+            assert_bus_widths(register, data);
+            let load = self.and([enable, selected]);
+            for (state, d) in register.bits().iter().zip(data.bits()) {
+                self.register(*state, *d, load, reset);
+            }
+        }
+    }
+
+    /// Returns the register selected by `address`.
+    pub fn read_register(&mut self, registers: &[Bus], address: &Bus) -> Bus {
+        self.bus_mux(&select_lines(address), registers)
+    }
+
+    /// Makes `pc` a program counter, which loads `target` when `jump` is high and otherwise
+    /// advances by one on every clock edge, wrapping around.
+    pub fn program_counter(&mut self, pc: &Bus, jump: NodeId, target: &Bus) {
+        let (next, _) = self.incrementer(pc);
+        let next = self.bus_mux(&[jump], &[next, target.clone()]);
+        self.set_next_bus(pc, &next);
+    }
+
+    fn signed_overflow(&mut self, a: &Bus, b: &Bus, result: &Bus, subtract: bool) -> NodeId {
+        let (Some(a), Some(b), Some(result)) =
+            (a.bits().last(), b.bits().last(), result.bits().last())
+        else {
+            return self.constant(false);
+        };
+        // Adding operands of the same sign, or subtracting one of the other sign, overflows if
+        // the sign of the result differs.
+        let signs_differ = self.parity([*a, *b]);
+        let may_overflow = if subtract {
+            signs_differ
+        } else {
+            self.not(signs_differ)
+        };
+        let sign_changed = self.parity([*a, *result]);
+        self.and([may_overflow, sign_changed])
+    }
+
+    /// A barrel shifter with one stage per bit of `amount` needed to shift across `a`.
+    fn shifter(&mut self, a: &Bus, amount: &Bus, left: bool) -> Bus {
+        let width = a.width();
+        let stages = width.next_power_of_two().trailing_zeros() as usize;
+        let mut shifted = a.clone();
+        for stage in 0..stages {
+            let distance = 1 << stage;
+            let zeros = self.constant_bus(0, distance);
+            let moved = if left {
+                Bus::concat([shifted.slice(..width - distance), zeros])
+            } else {
+                Bus::concat([zeros, shifted.slice(distance..)])
+            };
+            shifted = self.bus_mux(&[amount.bit(stage)], &[shifted, moved]);
+        }
+        shifted
+    }
+}
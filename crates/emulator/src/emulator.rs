@@ -1,98 +1,1059 @@
 use std::fmt::{Display, Formatter, Write};
-use std::mem::size_of;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::bus::Bus;
+use crate::logic::Logic;
+use crate::module::{Hierarchy, Module};
+use crate::netlist::{priority_encoder_width, Netlist, Node, NodeId};
+use crate::program::Program;
+use crate::value::Value;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
-    InputOutOfBounds { index: usize, input_count: usize },
-    InvalidInputCount { supplied: usize, expected: usize },
+    InputOutOfBounds {
+        index: usize,
+        input_count: usize,
+    },
+    InvalidInputCount {
+        supplied: usize,
+        expected: usize,
+    },
+    UnknownInput {
+        name: String,
+    },
+    DuplicateInput {
+        name: String,
+    },
+    MissingInput {
+        name: String,
+    },
+    TooManyInputs {
+        input_count: usize,
+        max: usize,
+    },
+    InvalidStateCount {
+        supplied: usize,
+        expected: usize,
+    },
+    CombinationalLoop {
+        nodes: Vec<NodeId>,
+    },
+    UndrivenWire {
+        node: NodeId,
+    },
+    Unstable {
+        nodes: Vec<NodeId>,
+        iterations: usize,
+    },
+    NotAnInput {
+        node: NodeId,
+    },
+    BusTooWide {
+        name: String,
+        width: usize,
+        max: usize,
+    },
+    ValueOutOfRange {
+        name: String,
+        value: u64,
+        width: usize,
+    },
+    PortWidthMismatch {
+        name: String,
+        supplied: usize,
+        expected: usize,
+    },
+    UnknownSignal {
+        name: String,
+    },
+    AddressOutOfRange {
+        address: usize,
+        address_width: usize,
+    },
+    InvalidHex {
+        line: usize,
+        word: String,
+    },
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    NoOutputs,
+    TableTooLarge {
+        rows: usize,
+        outputs: usize,
+        max_bytes: usize,
+    },
+    DuplicateInstance {
+        name: String,
+    },
+    DuplicateSignal {
+        name: String,
+    },
+}
+
+/// Largest input count for which [`Emulator::emulate_all`] builds a truth table.
+pub const MAX_TABLE_INPUTS: usize = 32;
+
+/// Largest truth table, in bytes, that [`Emulator::emulate_all`] and
+/// [`Emulator::emulate_all_logic`] allocate; enough for [`MAX_TABLE_INPUTS`] inputs and one output.
+pub const MAX_TABLE_BYTES: usize = 1 << 29;
+
+/// Number of times a circuit with feedback is evaluated before it is considered unstable.
+pub const DEFAULT_ITERATION_LIMIT: usize = 64;
+
+/// Smallest number of rows worth handing to another thread in [`Emulator::emulate_all`].
+const MIN_ROWS_PER_THREAD: usize = 1 << 14;
+
+/// The names of an emulator's inputs, in positional order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inputs {
+    names: Vec<String>,
+}
+
+impl Inputs {
+    pub fn new(names: impl IntoIterator<Item = impl Into<String>>) -> Result<Self> {
+        let mut inputs = Self { names: Vec::new() };
+        for name in names {
+            let name = name.into();
+            if inputs.names.contains(&name) {
+                return Err(Error::DuplicateInput { name });
+            }
+            inputs.names.push(name);
+        }
+        Ok(inputs)
+    }
+
+    /// Creates `count` inputs named `I0`, `I1`, ...
+    pub fn positional(count: usize) -> Self {
+        Self {
+            names: (0..count).map(|i| format!("I{i}")).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn index_of(&self, name: &str) -> Result<usize> {
+        self.names
+            .iter()
+            .position(|input| input == name)
+            .ok_or_else(|| Error::UnknownInput {
+                name: name.to_string(),
+            })
+    }
+
+    /// Returns an input component referring to the input called `name`.
+    pub fn input(&self, name: &str) -> Result<Component> {
+        self.index_of(name).map(input)
+    }
+}
+
+impl From<usize> for Inputs {
+    fn from(count: usize) -> Self {
+        Self::positional(count)
+    }
 }
 
 pub struct Emulator {
-    input_count: usize,
-    component: Component,
+    inputs: Inputs,
+    netlist: Netlist,
+    outputs: Vec<(String, NodeId)>,
+    program: Program,
+    state: Vec<bool>,
+    feedback: Vec<bool>,
+    iteration_limit: usize,
+    input_ports: Vec<Port>,
+    output_ports: Vec<Port>,
+    probes: Vec<String>,
+}
+
+/// A named group of inputs or outputs read as one integer.
+struct Port {
+    name: String,
+    /// The input indices or output positions of the bits, least significant first.
+    bits: Vec<usize>,
 }
 
 impl Emulator {
-    pub fn new(input_count: usize, component: Component) -> Result<Self> {
-        component.check_bounds(input_count)?;
+    /// Creates an emulator with a single output named `O1`.
+    ///
+    /// `inputs` is either an input count, naming the inputs `I0`, `I1`, ..., or [`Inputs`].
+    pub fn new(inputs: impl Into<Inputs>, component: Component) -> Result<Self> {
+        Self::with_outputs(inputs, [("O1", component)])
+    }
+
+    pub fn with_outputs(
+        inputs: impl Into<Inputs>,
+        outputs: impl IntoIterator<Item = (impl Into<String>, Component)>,
+    ) -> Result<Self> {
+        let inputs = inputs.into();
+        let mut netlist = Netlist::new();
+        let mut nodes = Vec::new();
+        for (name, component) in outputs {
+            component.check_bounds(inputs.len())?;
+            nodes.push((name, netlist.lower(&component)));
+        }
+        Self::from_netlist(inputs, netlist, nodes)
+    }
+
+    pub fn from_netlist(
+        inputs: impl Into<Inputs>,
+        netlist: Netlist,
+        outputs: impl IntoIterator<Item = (impl Into<String>, NodeId)>,
+    ) -> Result<Self> {
+        let inputs = inputs.into();
+        netlist.check_bounds(inputs.len())?;
+        let outputs = outputs
+            .into_iter()
+            .map(|(name, node)| (name.into(), node))
+            .collect::<Vec<_>>();
+        if outputs.is_empty() {
+            return Err(Error::NoOutputs);
+        }
+        for (name, node) in &outputs {
+            assert!(
+                node.index() < netlist.len(),
+                "Output `{name}` does not belong to the netlist"
+            );
+        }
+        let nodes = outputs.iter().map(|(_, node)| *node).collect::<Vec<_>>();
+        let program = Program::compile(&netlist, &nodes)?;
+        let state = vec![false; netlist.state_count()];
+        let feedback = vec![false; program.feedback_count()];
+        let single_bits = |names: Vec<&String>| {
+            names
+                .into_iter()
+                .enumerate()
+                .map(|(index, name)| Port {
+                    name: name.clone(),
+                    bits: vec![index],
+                })
+                .collect()
+        };
+        let input_ports = single_bits(inputs.names().iter().collect());
+        let output_ports = single_bits(outputs.iter().map(|(name, _)| name).collect());
         Ok(Self {
-            input_count,
-            component,
+            inputs,
+            netlist,
+            outputs,
+            program,
+            state,
+            feedback,
+            iteration_limit: DEFAULT_ITERATION_LIMIT,
+            input_ports,
+            output_ports,
+            probes: Vec::new(),
         })
     }
 
-    pub fn emulate(&self, inputs: &[bool]) -> Result<bool> {
-        if self.input_count != inputs.len() {
+    /// Creates an emulator whose ports are buses, for [`emulate_buses`](Self::emulate_buses).
+    ///
+    /// Every bit of an input bus must be an input node, and together the input buses must use
+    /// every input exactly once. Bit `i` of a bus `a` wider than one bit is named `a[i]`.
+    pub fn from_buses(
+        inputs: impl IntoIterator<Item = (impl Into<String>, Bus)>,
+        netlist: Netlist,
+        outputs: impl IntoIterator<Item = (impl Into<String>, Bus)>,
+    ) -> Result<Self> {
+        let inputs = inputs
+            .into_iter()
+            .map(|(name, bus)| (name.into(), bus))
+            .collect::<Vec<_>>();
+        let input_count = inputs.iter().map(|(_, bus)| bus.width()).sum();
+        let mut names = vec![String::new(); input_count];
+        let mut input_ports = Vec::new();
+        for (name, bus) in inputs {
+            check_bus_width(&name, &bus)?;
+            let mut bits = Vec::new();
+            for (bit, node) in bus.bits().iter().enumerate() {
+                let Node::Input { index } = *netlist.node(*node) else {
+                    return Err(Error::NotAnInput { node: *node });
+                };
+                if index >= input_count {
+                    return Err(Error::InputOutOfBounds { index, input_count });
+                }
+                if !names[index].is_empty() {
+                    return Err(Error::DuplicateInput {
+                        name: names[index].clone(),
+                    });
+                }
+                names[index] = bit_name(&name, bus.width(), bit);
+                bits.push(index);
+            }
+            input_ports.push(Port { name, bits });
+        }
+        let mut flattened = Vec::new();
+        let mut output_ports = Vec::new();
+        for (name, bus) in outputs {
+            let name = name.into();
+            check_bus_width(&name, &bus)?;
+            let first = flattened.len();
+            for (bit, node) in bus.bits().iter().enumerate().rev() {
+                flattened.push((bit_name(&name, bus.width(), bit), *node));
+            }
+            let bits = (first..flattened.len()).rev().collect();
+            output_ports.push(Port { name, bits });
+        }
+        let mut emulator = Self::from_netlist(Inputs::new(names)?, netlist, flattened)?;
+        emulator.input_ports = input_ports;
+        emulator.output_ports = output_ports;
+        Ok(emulator)
+    }
+
+    /// Creates an emulator with the ports of `module`.
+    ///
+    /// With [`Hierarchy::Keep`], every named signal of the module, including those of nested
+    /// instances such as `fa1.ha2.carry`, can be read with [`probe`](Self::probe).
+    pub fn from_module(module: &Module, hierarchy: Hierarchy) -> Result<Self> {
+        let mut emulator = Self::from_buses(
+            module.inputs().to_vec(),
+            module.netlist().clone(),
+            module.outputs().to_vec(),
+        )?;
+        if hierarchy == Hierarchy::Keep {
+            let (names, probes): (Vec<_>, Vec<_>) = module
+                .netlist()
+                .names()
+                .map(|(name, node)| (name.to_string(), node))
+                .unzip();
+            let outputs = emulator
+                .outputs
+                .iter()
+                .map(|(_, node)| *node)
+                .collect::<Vec<_>>();
+            emulator.program = Program::compile_with_probes(&emulator.netlist, &outputs, &probes)?;
+            emulator.probes = names;
+        }
+        Ok(emulator)
+    }
+
+    pub fn netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn inputs(&self) -> &Inputs {
+        &self.inputs
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn output_names(&self) -> impl Iterator<Item = &str> {
+        self.outputs.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn output_count(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Returns the names of the internal signals that can be [probed](Self::probe), in order.
+    pub fn probe_names(&self) -> impl Iterator<Item = &str> {
+        self.probes.iter().map(String::as_str)
+    }
+
+    /// Returns the value of the named internal signal, like [`emulate`](Self::emulate).
+    pub fn probe(&self, inputs: &[bool], name: &str) -> Result<bool> {
+        let probe = self
+            .probes
+            .iter()
+            .position(|probe| probe == name)
+            .ok_or_else(|| Error::UnknownSignal {
+                name: name.to_string(),
+            })?;
+        let mut values = Vec::new();
+        let state = self.state_values();
+        self.evaluate(inputs, &state, &mut self.feedback_values(), &mut values)?;
+        Ok(values[self.program.probe_registers()[probe] as usize])
+    }
+
+    /// Returns the current value of every state bit.
+    pub fn state(&self) -> &[bool] {
+        &self.state
+    }
+
+    pub fn set_state(&mut self, state: &[bool]) -> Result<()> {
+        if self.state.len() != state.len() {
+            return Err(Error::InvalidStateCount {
+                supplied: state.len(),
+                expected: self.state.len(),
+            });
+        }
+        self.state.copy_from_slice(state);
+        Ok(())
+    }
+
+    /// Clears every state bit and feedback wire.
+    pub fn reset(&mut self) {
+        self.state.fill(false);
+        self.feedback.fill(false);
+    }
+
+    /// Sets how often feedback loops are evaluated before [`Error::Unstable`] is reported.
+    pub fn set_iteration_limit(&mut self, iteration_limit: usize) {
+        self.iteration_limit = iteration_limit;
+    }
+
+    /// Returns the value of every output, in the order the outputs were declared.
+    ///
+    /// Sequential circuits are evaluated with their current state, which is left unchanged.
+    /// Feedback loops settle starting from the values they had after the last [`step`](Self::step).
+    pub fn emulate(&self, inputs: &[bool]) -> Result<Vec<bool>> {
+        self.emulate_values(inputs)
+    }
+
+    /// Like [`emulate`](Self::emulate), but in any [`Value`] algebra.
+    pub fn emulate_values<V: Value>(&self, inputs: &[V]) -> Result<Vec<V>> {
+        let mut values = Vec::new();
+        let state = self.state_values();
+        self.evaluate(inputs, &state, &mut self.feedback_values(), &mut values)?;
+        Ok(self.output_values(&values))
+    }
+
+    fn evaluate<V: Value>(
+        &self,
+        inputs: &[V],
+        state: &[V],
+        feedback: &mut [V],
+        values: &mut Vec<V>,
+    ) -> Result<()> {
+        if self.input_count() != inputs.len() {
             return Err(Error::InvalidInputCount {
                 supplied: inputs.len(),
-                expected: self.input_count,
+                expected: self.input_count(),
             });
         }
-        Ok(self.component.emulate(inputs))
+        self.program
+            .settle(inputs, state, feedback, values, self.iteration_limit)
+    }
+
+    fn output_values<V: Value>(&self, values: &[V]) -> Vec<V> {
+        self.program
+            .output_registers()
+            .iter()
+            .map(|register| values[*register as usize])
+            .collect()
+    }
+
+    fn state_values<V: Value>(&self) -> Vec<V> {
+        self.state
+            .iter()
+            .map(|state| V::from_bool(*state))
+            .collect()
+    }
+
+    fn feedback_values<V: Value>(&self) -> Vec<V> {
+        self.feedback
+            .iter()
+            .map(|value| V::from_bool(*value))
+            .collect()
+    }
+
+    /// Emulates one clock cycle: returns the outputs for the current state, then moves every state
+    /// bit to its next value.
+    pub fn step(&mut self, inputs: &[bool]) -> Result<Vec<bool>> {
+        let mut values = Vec::new();
+        let mut feedback = self.feedback.clone();
+        self.evaluate(inputs, &self.state, &mut feedback, &mut values)?;
+        self.feedback = feedback;
+        for (state, register) in self
+            .state
+            .iter_mut()
+            .zip(self.program.next_state_registers())
+        {
+            *state = values[*register as usize];
+        }
+        Ok(self.output_values(&values))
+    }
+
+    /// Steps the clock once per item of `cycles`, recording the inputs and outputs of every cycle.
+    pub fn simulate<I: AsRef<[bool]>>(
+        &mut self,
+        cycles: impl IntoIterator<Item = I>,
+    ) -> Result<Trace> {
+        let mut trace = Trace {
+            input_names: self.inputs.names().to_vec(),
+            output_names: self.output_names().map(String::from).collect(),
+            cycles: Vec::new(),
+        };
+        for inputs in cycles {
+            let inputs = inputs.as_ref();
+            let outputs = self.step(inputs)?;
+            trace.cycles.push((inputs.to_vec(), outputs));
+        }
+        Ok(trace)
+    }
+
+    /// Emulates the circuit in four-valued logic with its current state.
+    pub fn emulate_logic(&self, inputs: &[Logic]) -> Result<Vec<Logic>> {
+        self.emulate_values(inputs)
+    }
+
+    /// Like [`simulate`](Self::simulate), but in four-valued logic and starting with every state
+    /// bit and feedback wire unknown, which shows whether a reset sequence initialises the circuit.
+    ///
+    /// The emulator's own state is left unchanged.
+    pub fn simulate_logic<I: AsRef<[Logic]>>(
+        &self,
+        cycles: impl IntoIterator<Item = I>,
+    ) -> Result<Trace<Logic>> {
+        let mut trace = Trace {
+            input_names: self.inputs.names().to_vec(),
+            output_names: self.output_names().map(String::from).collect(),
+            cycles: Vec::new(),
+        };
+        let mut state = vec![Logic::X; self.state.len()];
+        let mut feedback = vec![Logic::X; self.feedback.len()];
+        let mut values = Vec::new();
+        for inputs in cycles {
+            let inputs = inputs.as_ref();
+            self.evaluate(inputs, &state, &mut feedback, &mut values)?;
+            for (state, register) in state.iter_mut().zip(self.program.next_state_registers()) {
+                *state = values[*register as usize];
+            }
+            trace
+                .cycles
+                .push((inputs.to_vec(), self.output_values(&values)));
+        }
+        Ok(trace)
+    }
+
+    /// Emulates every combination of `0`, `1`, `X` and `Z` inputs with the current state; fails
+    /// with [`Error::TooManyInputs`] if the circuit has more than half of [`MAX_TABLE_INPUTS`]
+    /// inputs, or with [`Error::TableTooLarge`] if the table, one byte per output value, would not
+    /// fit in [`MAX_TABLE_BYTES`].
+    pub fn emulate_all_logic(&self) -> Result<LogicTable> {
+        let input_count = self.input_count();
+        let max = MAX_TABLE_INPUTS / 2;
+        if input_count > max {
+            return Err(Error::TooManyInputs { input_count, max });
+        }
+        let row_count = 1usize << (2 * input_count);
+        check_table_size(row_count, self.outputs.len(), 8 * size_of::<Logic>())?;
+        let mut table = LogicTable {
+            input_names: self.inputs.names().to_vec(),
+            output_names: self.output_names().map(String::from).collect(),
+            row_count,
+            outputs: Vec::with_capacity(row_count * self.outputs.len()),
+        };
+        let state = self.state_values();
+        let mut values = Vec::new();
+        for row in 0..row_count {
+            let inputs = table.inputs(row);
+            let mut feedback = self.feedback_values();
+            self.evaluate(&inputs, &state, &mut feedback, &mut values)?;
+            table.outputs.extend(self.output_values(&values));
+        }
+        Ok(table)
     }
 
+    /// Emulates 64 input patterns at once; bit `i` of every word belongs to pattern `i`.
+    pub fn emulate_words(&self, inputs: &[u64]) -> Result<Vec<u64>> {
+        self.emulate_values(inputs)
+    }
+
+    /// Emulates the circuit with one integer per input port, returning one integer per output
+    /// port. Ports are buses for emulators created with [`from_buses`](Self::from_buses) and
+    /// single bits otherwise.
+    pub fn emulate_buses(&self, values: &[u64]) -> Result<Vec<u64>> {
+        let outputs = self.emulate(&self.bus_inputs(values)?)?;
+        Ok(self.bus_outputs(&outputs))
+    }
+
+    /// Like [`step`](Self::step), but with integers per port like
+    /// [`emulate_buses`](Self::emulate_buses).
+    pub fn step_buses(&mut self, values: &[u64]) -> Result<Vec<u64>> {
+        let outputs = self.step(&self.bus_inputs(values)?)?;
+        Ok(self.bus_outputs(&outputs))
+    }
+
+    fn bus_inputs(&self, values: &[u64]) -> Result<Vec<bool>> {
+        if self.input_ports.len() != values.len() {
+            return Err(Error::InvalidInputCount {
+                supplied: values.len(),
+                expected: self.input_ports.len(),
+            });
+        }
+        let mut inputs = vec![false; self.input_count()];
+        for (port, value) in self.input_ports.iter().zip(values) {
+            if port.bits.len() < 64 && value >> port.bits.len() != 0 {
+                return Err(Error::ValueOutOfRange {
+                    name: port.name.clone(),
+                    value: *value,
+                    width: port.bits.len(),
+                });
+            }
+            for (bit, index) in port.bits.iter().enumerate() {
+                inputs[*index] = value >> bit & 1 != 0;
+            }
+        }
+        Ok(inputs)
+    }
+
+    fn bus_outputs(&self, outputs: &[bool]) -> Vec<u64> {
+        self.output_ports
+            .iter()
+            .map(|port| {
+                port.bits
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, position)| u64::from(outputs[*position]) << bit)
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Emulates the circuit with the inputs assigned by name; every input must be assigned once.
+    pub fn emulate_named(&self, assignments: &[(&str, bool)]) -> Result<Vec<bool>> {
+        let mut inputs = vec![None; self.input_count()];
+        for (name, value) in assignments {
+            let index = self.inputs.index_of(name)?;
+            if inputs[index].replace(*value).is_some() {
+                return Err(Error::DuplicateInput {
+                    name: name.to_string(),
+                });
+            }
+        }
+        let inputs = inputs
+            .into_iter()
+            .zip(self.inputs.names())
+            .map(|(value, name)| value.ok_or_else(|| Error::MissingInput { name: name.clone() }))
+            .collect::<Result<Vec<_>>>()?;
+        self.emulate(&inputs)
+    }
+
+    /// Emulates every combination of inputs; fails with [`Error::TooManyInputs`] if the circuit
+    /// has more than [`MAX_TABLE_INPUTS`] inputs, or with [`Error::TableTooLarge`] if the table,
+    /// one bit per output value, would not fit in [`MAX_TABLE_BYTES`].
+    ///
+    /// Large tables are split across all available CPU cores.
     pub fn emulate_all(&self) -> Result<EmulationResult> {
-        assert!(
-            self.input_count < size_of::<usize>(),
-            "Too many inputs to emulate all possible states"
-        );
-        let count = 2usize.pow(self.input_count as u32);
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        self.emulate_all_with_threads(threads)
+    }
+
+    /// Like [`emulate_all`](Self::emulate_all), but uses at most `threads` threads.
+    pub fn emulate_all_with_threads(&self, threads: usize) -> Result<EmulationResult> {
+        let input_count = self.input_count();
+        let max = MAX_TABLE_INPUTS.min(usize::BITS as usize - 1);
+        if input_count > max {
+            return Err(Error::TooManyInputs { input_count, max });
+        }
+        let row_count = 1usize << input_count;
+        check_table_size(row_count, self.outputs.len(), 1)?;
+        let bit_count = row_count * self.outputs.len();
         let mut result = EmulationResult {
-            input_count: self.input_count,
-            states: Vec::with_capacity(count),
+            input_names: self.inputs.names().to_vec(),
+            output_names: self.output_names().map(String::from).collect(),
+            row_count,
+            states: vec![0; bit_count.div_ceil(u64::BITS as usize)],
         };
-        let mut inputs = vec![false; self.input_count];
-        for i in 0..count {
-            for bit_offset in 0..self.input_count {
-                let bit = (i >> bit_offset) & 1;
-                inputs[self.input_count - bit_offset - 1] = bit != 0;
-            }
-            let state = self.emulate(&inputs)?;
-            result.states.push(state);
+        // Chunks are a multiple of 64 rows, so every chunk starts at a word boundary.
+        let chunk_rows = row_count
+            .div_ceil(threads.max(1))
+            .next_multiple_of(64)
+            .max(MIN_ROWS_PER_THREAD);
+        if chunk_rows >= row_count {
+            self.fill_table(0, row_count, &mut result.states)?;
+            return Ok(result);
         }
+        let chunk_words = chunk_rows * self.outputs.len() / 64;
+        std::thread::scope(|scope| {
+            let handles = result
+                .states
+                .chunks_mut(chunk_words)
+                .enumerate()
+                .map(|(i, states)| {
+                    let first_row = i * chunk_rows;
+                    let rows = chunk_rows.min(row_count - first_row);
+                    scope.spawn(move || self.fill_table(first_row, rows, states))
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().unwrap())
+        })?;
         Ok(result)
     }
+
+    /// Emulates rows `first_row..first_row + rows` into `states`, starting at its first bit.
+    fn fill_table(&self, first_row: usize, rows: usize, states: &mut [u64]) -> Result<()> {
+        let mut inputs = vec![0; self.input_count()];
+        let state = self.state_values();
+        let mut values = Vec::with_capacity(self.program.register_count());
+        let mut bit = 0;
+        for base in (first_row..first_row + rows).step_by(64) {
+            fill_input_words(base as u64, &mut inputs);
+            self.evaluate(&inputs, &state, &mut self.feedback_values(), &mut values)?;
+            for lane in 0..(first_row + rows - base).min(64) {
+                for register in self.program.output_registers() {
+                    if values[*register as usize] >> lane & 1 != 0 {
+                        states[bit / 64] |= 1 << (bit % 64);
+                    }
+                    bit += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lazily emulates every combination of inputs, in the same order as
+    /// [`emulate_all`](Self::emulate_all), yielding `(inputs, outputs)` rows.
+    ///
+    /// Only circuits with feedback loops can yield errors, for rows on which they do not settle.
+    pub fn rows(&self) -> Result<Rows<'_>> {
+        let input_count = self.input_count();
+        let max = u64::BITS as usize - 1;
+        if input_count > max {
+            return Err(Error::TooManyInputs { input_count, max });
+        }
+        Ok(Rows {
+            emulator: self,
+            next: 0,
+            end: 1 << input_count,
+            values: Vec::with_capacity(self.program.register_count()),
+        })
+    }
+}
+
+fn check_bus_width(name: &str, bus: &Bus) -> Result<()> {
+    check_word_width(name, bus.width())
+}
+
+/// Checks that a bus of `width` bits fits in the `u64` words it is read and written as.
+pub(crate) fn check_word_width(name: &str, width: usize) -> Result<()> {
+    if width > 64 {
+        return Err(Error::BusTooWide {
+            name: name.to_string(),
+            width,
+            max: 64,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that a table of `rows` by `outputs` values of `value_bits` bits each fits in
+/// [`MAX_TABLE_BYTES`].
+fn check_table_size(rows: usize, outputs: usize, value_bits: usize) -> Result<()> {
+    let bits = rows
+        .checked_mul(outputs)
+        .and_then(|values| values.checked_mul(value_bits));
+    if bits.is_none_or(|bits| bits.div_ceil(8) > MAX_TABLE_BYTES) {
+        return Err(Error::TableTooLarge {
+            rows,
+            outputs,
+            max_bytes: MAX_TABLE_BYTES,
+        });
+    }
+    Ok(())
+}
+
+/// Names bit `bit` of a port, `name[bit]` unless the port is a single bit.
+pub(crate) fn bit_name(name: &str, width: usize, bit: usize) -> String {
+    if width == 1 {
+        name.to_string()
+    } else {
+        format!("{name}[{bit}]")
+    }
+}
+
+/// Sets `inputs` to the bits of `row`, most significant first.
+fn fill_inputs(row: u64, inputs: &mut [bool]) {
+    let input_count = inputs.len();
+    for bit_offset in 0..input_count {
+        inputs[input_count - bit_offset - 1] = (row >> bit_offset) & 1 != 0;
+    }
+}
+
+/// Bit `lane` of each pattern is bit `i` of `lane`.
+const LANE_PATTERNS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// Sets `inputs` to the words holding rows `base..base + 64`; `base` must be a multiple of 64.
+fn fill_input_words(base: u64, inputs: &mut [u64]) {
+    let input_count = inputs.len();
+    for bit_offset in 0..input_count {
+        inputs[input_count - bit_offset - 1] = match LANE_PATTERNS.get(bit_offset) {
+            Some(pattern) => *pattern,
+            None if (base >> bit_offset) & 1 != 0 => !0,
+            None => 0,
+        };
+    }
+}
+
+pub struct Rows<'a> {
+    emulator: &'a Emulator,
+    next: u64,
+    end: u64,
+    values: Vec<bool>,
+}
+
+impl Iterator for Rows<'_> {
+    type Item = Result<(Vec<bool>, Vec<bool>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            return None;
+        }
+        let mut inputs = vec![false; self.emulator.input_count()];
+        fill_inputs(self.next, &mut inputs);
+        self.next += 1;
+        let mut feedback = self.emulator.feedback.clone();
+        if let Err(error) = self.emulator.evaluate(
+            &inputs,
+            &self.emulator.state,
+            &mut feedback,
+            &mut self.values,
+        ) {
+            return Some(Err(error));
+        }
+        Some(Ok((inputs, self.emulator.output_values(&self.values))))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.end - self.next).ok();
+        (remaining.unwrap_or(usize::MAX), remaining)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self.next.saturating_add(n as u64).min(self.end);
+        self.next()
+    }
 }
 
 pub struct EmulationResult {
-    input_count: usize,
-    states: Vec<bool>,
+    input_names: Vec<String>,
+    output_names: Vec<String>,
+    row_count: usize,
+    /// The outputs of every row, one row after another, packed into bits.
+    states: Vec<u64>,
+}
+
+impl EmulationResult {
+    pub fn input_names(&self) -> &[String] {
+        &self.input_names
+    }
+
+    pub fn output_names(&self) -> &[String] {
+        &self.output_names
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Returns output `output` for the inputs whose bits, most significant first, form `row`.
+    pub fn output(&self, row: usize, output: usize) -> bool {
+        assert!(output < self.output_names.len(), "Output out of bounds");
+        let bit = row * self.output_names.len() + output;
+        self.states[bit / 64] >> (bit % 64) & 1 != 0
+    }
+
+    pub fn outputs(&self, row: usize) -> Vec<bool> {
+        (0..self.output_names.len())
+            .map(|output| self.output(row, output))
+            .collect()
+    }
 }
 
 impl Display for EmulationResult {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for i in 0..self.input_count {
-            f.write_fmt(format_args!("I{i:<2} "))?;
-        }
-        f.write_str("O1\n")?;
-        for (i, state) in self.states.iter().enumerate() {
-            for bit_offset in (0..self.input_count).rev() {
-                let bit = (i >> bit_offset) & 1;
-                f.write_fmt(format_args!("{bit}   "))?;
-            }
-            if *state {
-                f.write_char('1')?;
-            } else {
-                f.write_char('0')?;
-            }
-            f.write_char('\n')?;
+        let headers = self
+            .input_names
+            .iter()
+            .chain(&self.output_names)
+            .collect::<Vec<_>>();
+        let widths = headers
+            .iter()
+            .map(|header| header.len().max(3))
+            .collect::<Vec<_>>();
+        write_row(f, &widths, headers.iter().map(|header| header.as_str()))?;
+        let input_count = self.input_names.len();
+        for row in 0..self.row_count() {
+            let inputs = (0..input_count)
+                .rev()
+                .map(|bit_offset| (row >> bit_offset) & 1 != 0);
+            let cells = inputs
+                .chain(self.outputs(row))
+                .map(|state| if state { "1" } else { "0" });
+            write_row(f, &widths, cells)?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of [`Emulator::emulate_all_logic`].
+pub struct LogicTable {
+    input_names: Vec<String>,
+    output_names: Vec<String>,
+    row_count: usize,
+    outputs: Vec<Logic>,
+}
+
+impl LogicTable {
+    pub fn input_names(&self) -> &[String] {
+        &self.input_names
+    }
+
+    pub fn output_names(&self) -> &[String] {
+        &self.output_names
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Returns the inputs of `row`; every input takes the values of [`Logic::ALL`] in turn, the
+    /// first input changing slowest.
+    pub fn inputs(&self, row: usize) -> Vec<Logic> {
+        let input_count = self.input_names.len();
+        (0..input_count)
+            .rev()
+            .map(|offset| Logic::ALL[(row >> (2 * offset)) & 3])
+            .collect()
+    }
+
+    pub fn outputs(&self, row: usize) -> &[Logic] {
+        let output_count = self.output_names.len();
+        &self.outputs[row * output_count..(row + 1) * output_count]
+    }
+}
+
+impl Display for LogicTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let headers = self
+            .input_names
+            .iter()
+            .chain(&self.output_names)
+            .collect::<Vec<_>>();
+        let widths = headers
+            .iter()
+            .map(|header| header.len().max(3))
+            .collect::<Vec<_>>();
+        write_row(f, &widths, headers.iter().map(|header| header.as_str()))?;
+        for row in 0..self.row_count {
+            let cells = self
+                .inputs(row)
+                .into_iter()
+                .chain(self.outputs(row).iter().copied())
+                .map(|value| value.symbol().to_string())
+                .collect::<Vec<_>>();
+            write_row(f, &widths, cells.iter().map(String::as_str))?;
+        }
+        Ok(())
+    }
+}
+
+/// The inputs and outputs of every cycle of [`Emulator::simulate`] or
+/// [`Emulator::simulate_logic`].
+pub struct Trace<T = bool> {
+    input_names: Vec<String>,
+    output_names: Vec<String>,
+    cycles: Vec<(Vec<T>, Vec<T>)>,
+}
+
+impl<T> Trace<T> {
+    pub fn cycle_count(&self) -> usize {
+        self.cycles.len()
+    }
+
+    pub fn inputs(&self, cycle: usize) -> &[T] {
+        &self.cycles[cycle].0
+    }
+
+    pub fn outputs(&self, cycle: usize) -> &[T] {
+        &self.cycles[cycle].1
+    }
+}
+
+impl<T: Copy + Into<Logic>> Display for Trace<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let headers = ["cycle".to_string()]
+            .iter()
+            .chain(&self.input_names)
+            .chain(&self.output_names)
+            .cloned()
+            .collect::<Vec<_>>();
+        let widths = headers
+            .iter()
+            .map(|header| header.len().max(3))
+            .collect::<Vec<_>>();
+        write_row(f, &widths, headers.iter().map(String::as_str))?;
+        for (cycle, (inputs, outputs)) in self.cycles.iter().enumerate() {
+            let cycle = cycle.to_string();
+            let cells = inputs
+                .iter()
+                .chain(outputs)
+                .map(|value| (*value).into().symbol().to_string())
+                .collect::<Vec<_>>();
+            let cells = [cycle.as_str()]
+                .into_iter()
+                .chain(cells.iter().map(String::as_str));
+            write_row(f, &widths, cells)?;
         }
         Ok(())
     }
 }
 
+fn write_row<'a>(
+    f: &mut Formatter<'_>,
+    widths: &[usize],
+    cells: impl Iterator<Item = &'a str>,
+) -> std::fmt::Result {
+    let mut line = String::new();
+    for (cell, width) in cells.zip(widths) {
+        line.write_fmt(format_args!("{cell:<width$} "))?;
+    }
+    f.write_str(line.trim_end())?;
+    f.write_char('\n')
+}
+
+#[derive(Clone)]
 pub enum Component {
     Input { index: usize },
     Not(NotGate),
     Or(OrGate),
     And(AndGate),
-    Xor(XorGate),
+    OneHot(OneHotGate),
+    Parity(ParityGate),
+    Majority(MajorityGate),
+    Threshold(ThresholdGate),
+    Nand(NandGate),
+    Nor(NorGate),
+    Xnor(XnorGate),
+    Buffer(BufferGate),
+    Const(bool),
+    Mux(MuxGate),
+    Demux(DemuxGate),
+    Decoder(DecoderGate),
+    PriorityEncoder(PriorityEncoderGate),
 }
 
 impl Component {
-    fn check_bounds(&self, input_count: usize) -> Result<()> {
+    pub fn check_bounds(&self, input_count: usize) -> Result<()> {
         match self {
             Component::Input { index } => {
                 if *index >= input_count {
@@ -106,17 +1067,67 @@ impl Component {
             Component::Not(not) => not.check_bounds(input_count),
             Component::Or(or) => or.check_bounds(input_count),
             Component::And(and) => and.check_bounds(input_count),
-            Component::Xor(xor) => xor.check_bounds(input_count),
+            Component::OneHot(one_hot) => one_hot.check_bounds(input_count),
+            Component::Parity(parity) => parity.check_bounds(input_count),
+            Component::Majority(majority) => majority.check_bounds(input_count),
+            Component::Threshold(threshold) => threshold.check_bounds(input_count),
+            Component::Nand(nand) => nand.check_bounds(input_count),
+            Component::Nor(nor) => nor.check_bounds(input_count),
+            Component::Xnor(xnor) => xnor.check_bounds(input_count),
+            Component::Buffer(buffer) => buffer.check_bounds(input_count),
+            Component::Const(_) => Ok(()),
+            Component::Mux(mux) => mux.check_bounds(input_count),
+            Component::Demux(demux) => demux.check_bounds(input_count),
+            Component::Decoder(decoder) => decoder.check_bounds(input_count),
+            Component::PriorityEncoder(encoder) => encoder.check_bounds(input_count),
         }
     }
 
-    fn emulate(&self, inputs: &[bool]) -> bool {
+    /// Evaluates the tree directly in any [`Value`] algebra, without lowering it into a
+    /// [`Netlist`].
+    ///
+    /// Panics if an input is out of bounds.
+    pub fn emulate<V: Value>(&self, inputs: &[V]) -> V {
         match self {
             Component::Input { index } => inputs[*index],
             Component::Not(not) => not.emulate(inputs),
             Component::Or(or) => or.emulate(inputs),
             Component::And(and) => and.emulate(inputs),
-            Component::Xor(xor) => xor.emulate(inputs),
+            Component::OneHot(one_hot) => one_hot.emulate(inputs),
+            Component::Parity(parity) => parity.emulate(inputs),
+            Component::Majority(majority) => majority.emulate(inputs),
+            Component::Threshold(threshold) => threshold.emulate(inputs),
+            Component::Nand(nand) => nand.emulate(inputs),
+            Component::Nor(nor) => nor.emulate(inputs),
+            Component::Xnor(xnor) => xnor.emulate(inputs),
+            Component::Buffer(buffer) => buffer.emulate(inputs),
+            Component::Const(value) => V::from_bool(*value),
+            Component::Mux(mux) => mux.emulate(inputs),
+            Component::Demux(demux) => demux.emulate(inputs),
+            Component::Decoder(decoder) => decoder.emulate(inputs),
+            Component::PriorityEncoder(encoder) => encoder.emulate(inputs),
+        }
+    }
+
+    pub(crate) fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        match self {
+            Component::Input { index } => netlist.input(*index),
+            Component::Not(not) => not.lower(netlist),
+            Component::Or(or) => or.lower(netlist),
+            Component::And(and) => and.lower(netlist),
+            Component::OneHot(one_hot) => one_hot.lower(netlist),
+            Component::Parity(parity) => parity.lower(netlist),
+            Component::Majority(majority) => majority.lower(netlist),
+            Component::Threshold(threshold) => threshold.lower(netlist),
+            Component::Nand(nand) => nand.lower(netlist),
+            Component::Nor(nor) => nor.lower(netlist),
+            Component::Xnor(xnor) => xnor.lower(netlist),
+            Component::Buffer(buffer) => buffer.lower(netlist),
+            Component::Const(value) => netlist.constant(*value),
+            Component::Mux(mux) => mux.lower(netlist),
+            Component::Demux(demux) => demux.lower(netlist),
+            Component::Decoder(decoder) => decoder.lower(netlist),
+            Component::PriorityEncoder(encoder) => encoder.lower(netlist),
         }
     }
 }
@@ -125,6 +1136,7 @@ pub fn input(index: usize) -> Component {
     Component::Input { index }
 }
 
+#[derive(Clone)]
 pub struct NotGate {
     input: Box<Component>,
 }
@@ -134,8 +1146,13 @@ impl NotGate {
         self.input.check_bounds(input_count)
     }
 
-    fn emulate(&self, inputs: &[bool]) -> bool {
-        !self.input.emulate(inputs)
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        self.input.emulate(inputs).not()
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let input = self.input.lower(netlist);
+        netlist.not(input)
     }
 }
 
@@ -145,6 +1162,7 @@ pub fn not(component: Component) -> Component {
     })
 }
 
+#[derive(Clone)]
 pub struct OrGate {
     inputs: Vec<Component>,
 }
@@ -157,13 +1175,24 @@ impl OrGate {
         Ok(())
     }
 
-    fn emulate(&self, inputs: &[bool]) -> bool {
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let mut result = V::ZERO;
         for input in &self.inputs {
-            if input.emulate(inputs) {
-                return true;
+            result = result.or(input.emulate(inputs));
+            if result == V::ONE {
+                return result;
             }
         }
-        false
+        result
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.or(inputs)
     }
 }
 
@@ -176,6 +1205,7 @@ pub fn or(components: impl IntoIterator<Item = Component>) -> Component {
     Component::Or(OrGate { inputs })
 }
 
+#[derive(Clone)]
 pub struct AndGate {
     inputs: Vec<Component>,
 }
@@ -188,13 +1218,24 @@ impl AndGate {
         Ok(())
     }
 
-    fn emulate(&self, inputs: &[bool]) -> bool {
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let mut result = V::ONE;
         for input in &self.inputs {
-            if !input.emulate(inputs) {
-                return false;
+            result = result.and(input.emulate(inputs));
+            if result == V::ZERO {
+                return result;
             }
         }
-        true
+        result
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.and(inputs)
     }
 }
 
@@ -207,11 +1248,13 @@ pub fn and(components: impl IntoIterator<Item = Component>) -> Component {
     Component::And(AndGate { inputs })
 }
 
-pub struct XorGate {
+/// True if exactly one input is true.
+#[derive(Clone)]
+pub struct OneHotGate {
     inputs: Vec<Component>,
 }
 
-impl XorGate {
+impl OneHotGate {
     fn check_bounds(&self, input_count: usize) -> Result<()> {
         for input in &self.inputs {
             input.check_bounds(input_count)?;
@@ -219,26 +1262,502 @@ impl XorGate {
         Ok(())
     }
 
-    fn emulate(&self, inputs: &[bool]) -> bool {
-        let mut check = false;
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        V::one_hot(self.inputs.iter().map(|input| input.emulate(inputs)))
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.one_hot(inputs)
+    }
+}
+
+pub fn one_hot(components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(
+        inputs.len() > 1,
+        "One-hot gate requires at least two inputs"
+    );
+    Component::OneHot(OneHotGate { inputs })
+}
+
+/// True if an odd number of inputs is true, like a Verilog `^` reduction.
+#[derive(Clone)]
+pub struct ParityGate {
+    inputs: Vec<Component>,
+}
+
+impl ParityGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
         for input in &self.inputs {
-            let result = input.emulate(inputs);
-            if result && check {
-                return false;
+            input.check_bounds(input_count)?;
+        }
+        Ok(())
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        V::parity(self.inputs.iter().map(|input| input.emulate(inputs)))
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.parity(inputs)
+    }
+}
+
+pub fn parity(components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(inputs.len() > 1, "Parity gate requires at least two inputs");
+    Component::Parity(ParityGate { inputs })
+}
+
+/// True if more than half of its inputs are true.
+#[derive(Clone)]
+pub struct MajorityGate {
+    inputs: Vec<Component>,
+}
+
+impl MajorityGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        check_each(&self.inputs, input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let k = self.inputs.len() / 2 + 1;
+        V::threshold(k, emulate_each(&self.inputs, inputs))
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = lower_each(&self.inputs, netlist);
+        netlist.majority(inputs)
+    }
+}
+
+pub fn majority(components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(
+        inputs.len() > 1,
+        "Majority gate requires at least two inputs"
+    );
+    Component::Majority(MajorityGate { inputs })
+}
+
+/// True if at least `k` of its inputs are true.
+#[derive(Clone)]
+pub struct ThresholdGate {
+    k: usize,
+    inputs: Vec<Component>,
+}
+
+impl ThresholdGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        check_each(&self.inputs, input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        V::threshold(self.k, emulate_each(&self.inputs, inputs))
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = lower_each(&self.inputs, netlist);
+        netlist.threshold(self.k, inputs)
+    }
+}
+
+/// Builds a gate that is true if at least `k` inputs are; [`or`] and [`and`] are the cases
+/// `k = 1` and `k = n`.
+pub fn threshold(k: usize, components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(
+        inputs.len() > 1,
+        "Threshold gate requires at least two inputs"
+    );
+    assert!(
+        (1..=inputs.len()).contains(&k),
+        "Threshold gate requires a threshold between 1 and its input count"
+    );
+    Component::Threshold(ThresholdGate { k, inputs })
+}
+
+/// A one-hot gate, which only agrees with [`parity`] for two inputs.
+#[deprecated(note = "use `parity` or `one_hot`, which differ for more than two inputs")]
+pub fn xor(components: impl IntoIterator<Item = Component>) -> Component {
+    one_hot(components)
+}
+
+#[derive(Clone)]
+pub struct NandGate {
+    inputs: Vec<Component>,
+}
+
+impl NandGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        for input in &self.inputs {
+            input.check_bounds(input_count)?;
+        }
+        Ok(())
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let mut result = V::ONE;
+        for input in &self.inputs {
+            result = result.and(input.emulate(inputs));
+            if result == V::ZERO {
+                break;
             }
-            if result {
-                check = true;
+        }
+        result.not()
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.nand(inputs)
+    }
+}
+
+pub fn nand(components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(inputs.len() > 1, "Nand gate requires at least two inputs");
+    Component::Nand(NandGate { inputs })
+}
+
+#[derive(Clone)]
+pub struct NorGate {
+    inputs: Vec<Component>,
+}
+
+impl NorGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        for input in &self.inputs {
+            input.check_bounds(input_count)?;
+        }
+        Ok(())
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let mut result = V::ZERO;
+        for input in &self.inputs {
+            result = result.or(input.emulate(inputs));
+            if result == V::ONE {
+                break;
             }
         }
-        check
+        result.not()
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.nor(inputs)
     }
 }
 
-pub fn xor(components: impl IntoIterator<Item = Component>) -> Component {
-    let mut inputs = Vec::new();
-    for component in components.into_iter() {
-        inputs.push(component);
+pub fn nor(components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(inputs.len() > 1, "Nor gate requires at least two inputs");
+    Component::Nor(NorGate { inputs })
+}
+
+/// The complement of [`ParityGate`], like a Verilog `~^` reduction.
+#[derive(Clone)]
+pub struct XnorGate {
+    inputs: Vec<Component>,
+}
+
+impl XnorGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        for input in &self.inputs {
+            input.check_bounds(input_count)?;
+        }
+        Ok(())
     }
-    assert!(inputs.len() > 1, "And gate requires at least two inputs");
-    Component::Xor(XorGate { inputs })
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        V::parity(self.inputs.iter().map(|input| input.emulate(inputs))).not()
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.lower(netlist))
+            .collect::<Vec<_>>();
+        netlist.xnor(inputs)
+    }
+}
+
+pub fn xnor(components: impl IntoIterator<Item = Component>) -> Component {
+    let inputs = components.into_iter().collect::<Vec<_>>();
+    assert!(inputs.len() > 1, "Xnor gate requires at least two inputs");
+    Component::Xnor(XnorGate { inputs })
+}
+
+/// Passes its input through unchanged.
+#[derive(Clone)]
+pub struct BufferGate {
+    input: Box<Component>,
+}
+
+impl BufferGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        self.input.check_bounds(input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        self.input.emulate(inputs)
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        self.input.lower(netlist)
+    }
+}
+
+pub fn buffer(component: Component) -> Component {
+    Component::Buffer(BufferGate {
+        input: Box::new(component),
+    })
+}
+
+/// A signal tied to `value`.
+pub fn constant(value: bool) -> Component {
+    Component::Const(value)
+}
+
+fn check_each(components: &[Component], input_count: usize) -> Result<()> {
+    for component in components {
+        component.check_bounds(input_count)?;
+    }
+    Ok(())
+}
+
+fn emulate_each<V: Value>(components: &[Component], inputs: &[V]) -> Vec<V> {
+    components
+        .iter()
+        .map(|component| component.emulate(inputs))
+        .collect()
+}
+
+fn lower_each(components: &[Component], netlist: &mut Netlist) -> Vec<NodeId> {
+    components
+        .iter()
+        .map(|component| component.lower(netlist))
+        .collect()
+}
+
+/// True if `select`, most significant bit first, equals `index`.
+fn minterm<V: Value>(select: &[V], index: usize) -> V {
+    select
+        .iter()
+        .rev()
+        .enumerate()
+        .fold(V::ONE, |minterm, (bit, value)| {
+            if index >> bit & 1 != 0 {
+                minterm.and(*value)
+            } else {
+                minterm.and(value.not())
+            }
+        })
+}
+
+/// Selects one of `2^n` inputs with `n` select lines, the first being the most significant.
+#[derive(Clone)]
+pub struct MuxGate {
+    select: Vec<Component>,
+    inputs: Vec<Component>,
+}
+
+impl MuxGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        check_each(&self.select, input_count)?;
+        check_each(&self.inputs, input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let select = emulate_each(&self.select, inputs);
+        self.inputs
+            .iter()
+            .enumerate()
+            .fold(V::ZERO, |result, (index, input)| {
+                result.or(minterm(&select, index).and(input.emulate(inputs)))
+            })
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let select = lower_each(&self.select, netlist);
+        let inputs = lower_each(&self.inputs, netlist);
+        netlist.mux(&select, &inputs)
+    }
+}
+
+pub fn mux(
+    select: impl IntoIterator<Item = Component>,
+    inputs: impl IntoIterator<Item = Component>,
+) -> Component {
+    let select = select.into_iter().collect::<Vec<_>>();
+    let inputs = inputs.into_iter().collect::<Vec<_>>();
+    assert!(
+        !select.is_empty() && inputs.len() == 1 << select.len(),
+        "Multiplexer requires one input per value of its select lines"
+    );
+    Component::Mux(MuxGate { select, inputs })
+}
+
+/// Output `output` of a demultiplexer, which is the input if the select lines equal `output`.
+///
+/// Every output shares the select lines and input of the demultiplexer.
+#[derive(Clone)]
+pub struct DemuxGate {
+    select: Rc<[Component]>,
+    input: Rc<Component>,
+    output: usize,
+}
+
+impl DemuxGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        check_each(&self.select, input_count)?;
+        self.input.check_bounds(input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let select = emulate_each(&self.select, inputs);
+        minterm(&select, self.output).and(self.input.emulate(inputs))
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let select = lower_each(&self.select, netlist);
+        let input = self.input.lower(netlist);
+        netlist.demux(&select, input)[self.output]
+    }
+}
+
+/// Returns the `2^n` outputs of a demultiplexer with `n` select lines.
+pub fn demux(select: impl IntoIterator<Item = Component>, input: Component) -> Vec<Component> {
+    let select = select.into_iter().collect::<Vec<_>>();
+    assert!(
+        !select.is_empty(),
+        "Demultiplexer requires at least one select line"
+    );
+    let select = Rc::<[Component]>::from(select);
+    let input = Rc::new(input);
+    (0..1 << select.len())
+        .map(|output| {
+            Component::Demux(DemuxGate {
+                select: Rc::clone(&select),
+                input: Rc::clone(&input),
+                output,
+            })
+        })
+        .collect()
+}
+
+/// Output `output` of a decoder, which is true if the select lines equal `output`.
+#[derive(Clone)]
+pub struct DecoderGate {
+    select: Rc<[Component]>,
+    output: usize,
+}
+
+impl DecoderGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        check_each(&self.select, input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        minterm(&emulate_each(&self.select, inputs), self.output)
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let select = lower_each(&self.select, netlist);
+        netlist.decoder(&select)[self.output]
+    }
+}
+
+/// Returns the `2^n` one-hot outputs of a decoder with `n` select lines.
+pub fn decoder(select: impl IntoIterator<Item = Component>) -> Vec<Component> {
+    let select = select.into_iter().collect::<Vec<_>>();
+    assert!(
+        !select.is_empty(),
+        "Decoder requires at least one select line"
+    );
+    let select = Rc::<[Component]>::from(select);
+    (0..1 << select.len())
+        .map(|output| {
+            Component::Decoder(DecoderGate {
+                select: Rc::clone(&select),
+                output,
+            })
+        })
+        .collect()
+}
+
+/// Output `output` of a priority encoder; see [`priority_encoder`].
+#[derive(Clone)]
+pub struct PriorityEncoderGate {
+    inputs: Rc<[Component]>,
+    output: usize,
+}
+
+impl PriorityEncoderGate {
+    fn check_bounds(&self, input_count: usize) -> Result<()> {
+        check_each(&self.inputs, input_count)
+    }
+
+    fn emulate<V: Value>(&self, inputs: &[V]) -> V {
+        let values = emulate_each(&self.inputs, inputs);
+        let width = priority_encoder_width(values.len());
+        // `higher` is true if any input after the current one is.
+        let mut higher = V::ZERO;
+        let mut result = V::ZERO;
+        for (index, value) in values.iter().enumerate().rev() {
+            let active = value.and(higher.not());
+            if self.output < width && index >> (width - 1 - self.output) & 1 != 0 {
+                result = result.or(active);
+            }
+            higher = higher.or(*value);
+        }
+        if self.output == width {
+            higher
+        } else {
+            result
+        }
+    }
+
+    fn lower(&self, netlist: &mut Netlist) -> NodeId {
+        let inputs = lower_each(&self.inputs, netlist);
+        netlist.priority_encoder(&inputs)[self.output]
+    }
+}
+
+/// Returns the index of the last true input as `ceil(log2(n))` bits, most significant first,
+/// followed by an output that is true if any input is.
+pub fn priority_encoder(inputs: impl IntoIterator<Item = Component>) -> Vec<Component> {
+    let inputs = inputs.into_iter().collect::<Vec<_>>();
+    assert!(
+        inputs.len() > 1,
+        "Priority encoder requires at least two inputs"
+    );
+    let inputs = Rc::<[Component]>::from(inputs);
+    (0..=priority_encoder_width(inputs.len()))
+        .map(|output| {
+            Component::PriorityEncoder(PriorityEncoderGate {
+                inputs: Rc::clone(&inputs),
+                output,
+            })
+        })
+        .collect()
 }
@@ -1,3 +1,12 @@
-pub use emulator_macros::emulator;
+pub use emulator_macros::{emulator, include_circuit};
 
+pub mod arithmetic;
+pub mod bus;
+pub mod cpu;
 pub mod emulator;
+pub mod logic;
+pub mod memory;
+pub mod module;
+pub mod netlist;
+pub mod program;
+pub mod value;
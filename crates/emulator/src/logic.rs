@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+/// A four-valued logic level.
+///
+/// Gates treat `Z` like `X` and propagate unknowns pessimistically: the result is only known if
+/// it is the same for every possible value of the unknown inputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Logic {
+    Zero,
+    One,
+    /// Unknown, e.g. an uninitialised state bit.
+    #[default]
+    X,
+    /// High impedance, e.g. an undriven tri-state bus.
+    Z,
+}
+
+impl Logic {
+    /// Every value, in the order rows are enumerated by [`Emulator::emulate_all_logic`].
+    ///
+    /// [`Emulator::emulate_all_logic`]: crate::emulator::Emulator::emulate_all_logic
+    pub const ALL: [Logic; 4] = [Logic::Zero, Logic::One, Logic::X, Logic::Z];
+
+    /// Returns the value as a `bool`, or `None` for `X` and `Z`.
+    pub fn to_bool(self) -> Option<bool> {
+        match self {
+            Logic::Zero => Some(false),
+            Logic::One => Some(true),
+            Logic::X | Logic::Z => None,
+        }
+    }
+
+    pub fn is_known(self) -> bool {
+        self.to_bool().is_some()
+    }
+
+    /// Returns `One` if exactly one value is `One`.
+    pub fn one_hot(values: impl IntoIterator<Item = Logic>) -> Logic {
+        let mut ones = 0;
+        let mut unknowns = 0;
+        for value in values {
+            match value.to_bool() {
+                Some(true) => ones += 1,
+                Some(false) => {}
+                None => unknowns += 1,
+            }
+        }
+        match (ones, unknowns) {
+            (2.., _) => Logic::Zero,
+            (ones, 0) => Logic::from(ones == 1),
+            _ => Logic::X,
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Logic::Zero => '0',
+            Logic::One => '1',
+            Logic::X => 'X',
+            Logic::Z => 'Z',
+        }
+    }
+}
+
+impl From<bool> for Logic {
+    fn from(value: bool) -> Self {
+        if value {
+            Logic::One
+        } else {
+            Logic::Zero
+        }
+    }
+}
+
+impl Not for Logic {
+    type Output = Logic;
+
+    fn not(self) -> Logic {
+        match self.to_bool() {
+            Some(value) => Logic::from(!value),
+            None => Logic::X,
+        }
+    }
+}
+
+impl BitAnd for Logic {
+    type Output = Logic;
+
+    fn bitand(self, rhs: Logic) -> Logic {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(false), _) | (_, Some(false)) => Logic::Zero,
+            (Some(true), Some(true)) => Logic::One,
+            _ => Logic::X,
+        }
+    }
+}
+
+impl BitOr for Logic {
+    type Output = Logic;
+
+    fn bitor(self, rhs: Logic) -> Logic {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(true), _) | (_, Some(true)) => Logic::One,
+            (Some(false), Some(false)) => Logic::Zero,
+            _ => Logic::X,
+        }
+    }
+}
+
+impl BitXor for Logic {
+    type Output = Logic;
+
+    fn bitxor(self, rhs: Logic) -> Logic {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(a), Some(b)) => Logic::from(a ^ b),
+            _ => Logic::X,
+        }
+    }
+}
+
+impl Display for Logic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use crate::bus::{select_lines, Bus};
+use crate::emulator::{check_word_width, Emulator, Error, Result};
+use crate::netlist::{Netlist, Node, NodeId};
+
+/// Widest address bus a memory accepts; every address is decoded into its own gate.
+pub const MAX_ADDRESS_WIDTH: usize = 16;
+
+/// The contents of a read-only memory, one word per address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rom {
+    width: usize,
+    words: Vec<u64>,
+}
+
+impl Rom {
+    /// Creates a ROM of `width`-bit words; words are at most 64 bits wide.
+    pub fn new(width: usize, words: impl Into<Vec<u64>>) -> Result<Self> {
+        let words = words.into();
+        check_word_width("data", width)?;
+        check_words(width, &words)?;
+        Ok(Self { width, words })
+    }
+
+    /// Creates a ROM with one byte per word.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            width: 8,
+            words: bytes.iter().map(|byte| u64::from(*byte)).collect(),
+        }
+    }
+
+    /// Loads a ROM of `width`-bit words from a file in the format of [`parse_hex`].
+    pub fn from_hex_file(width: usize, path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(width, read_hex_file(path)?)
+    }
+
+    /// Loads a ROM with one byte of the file per word.
+    pub fn from_binary_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::from_bytes(&read_binary_file(path)?))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Checks that an address bus of `address_width` bits can reach every word.
+    pub fn check_bounds(&self, address_width: usize) -> Result<()> {
+        check_address_width(address_width)?;
+        check_addresses(self.words.len(), address_width)
+    }
+}
+
+/// The state of a memory added with [`Netlist::ram`].
+#[derive(Clone, Debug)]
+pub struct Ram {
+    words: Vec<Bus>,
+    read: Bus,
+}
+
+impl Ram {
+    /// Returns the state bits of every word, by address.
+    pub fn words(&self) -> &[Bus] {
+        &self.words
+    }
+
+    /// Returns the word at the address currently on the address bus.
+    pub fn read(&self) -> &Bus {
+        &self.read
+    }
+
+    pub fn address_width(&self) -> usize {
+        self.words.len().trailing_zeros() as usize
+    }
+}
+
+impl Netlist {
+    /// Returns the word of `rom` selected by `address`; addresses past the end read as zero.
+    pub fn rom(&mut self, rom: &Rom, address: &Bus) -> Result<Bus> {
+        rom.check_bounds(address.width())?;
+        if address.width() == 0 {
+            let word = rom.words.first().copied().unwrap_or(0);
+            return Ok(self.constant_bus(word, rom.width));
+        }
+        let selected = self.decoder(&select_lines(address));
+        let bits = (0..rom.width)
+            .map(|bit| {
+                let terms = rom
+                    .words
+                    .iter()
+                    .zip(&selected)
+                    .filter(|(word, _)| *word >> bit & 1 != 0)
+                    .map(|(_, selected)| *selected)
+                    .collect::<Vec<_>>();
+                self.any(&terms)
+            })
+            .collect::<Vec<_>>();
+        Ok(Bus::new(bits))
+    }
+
+    /// Adds a synchronous RAM with one word of the width of `data`, at most 64 bits, per value of
+    /// `address`.
+    ///
+    /// The word at `address` can be read at any time, and is replaced by `data` on every clock
+    /// edge with `write_enable` high.
+    pub fn ram(&mut self, address: &Bus, data: &Bus, write_enable: NodeId) -> Result<Ram> {
+        check_address_width(address.width())?;
+        check_word_width("data", data.width())?;
+        let words = (0..1 << address.width())
+            .map(|_| self.state_bus(data.width()))
+            .collect::<Vec<_>>();
+        self.register_file(&words, address, data, write_enable);
+        let read = self.read_register(&words, address);
+        Ok(Ram { words, read })
+    }
+}
+
+impl Emulator {
+    /// Sets the words of `ram` from address zero on, leaving the remaining words unchanged.
+    pub fn load_ram(&mut self, ram: &Ram, words: &[u64]) -> Result<()> {
+        let width = ram.words.first().map_or(0, Bus::width);
+        check_words(width, words)?;
+        check_addresses(words.len(), ram.address_width())?;
+        let mut state = self.state().to_vec();
+        for (bus, word) in ram.words.iter().zip(words) {
+            for (bit, node) in bus.bits().iter().enumerate() {
+                state[self.state_index(*node)] = word >> bit & 1 != 0;
+            }
+        }
+        self.set_state(&state)
+    }
+
+    /// Returns the current value of every word of `ram`, by address.
+    pub fn ram_contents(&self, ram: &Ram) -> Vec<u64> {
+        ram.words
+            .iter()
+            .map(|bus| {
+                bus.bits()
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, node)| u64::from(self.state()[self.state_index(*node)]) << bit)
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn state_index(&self, node: NodeId) -> usize {
+        match self.netlist().node(node) {
+            Node::State { index } => *index,
+            _ => panic!("Memory does not belong to this emulator"),
+        }
+    }
+}
+
+/// Parses whitespace-separated hexadecimal words, a subset of Verilog's `$readmemh` format.
+///
+/// Words are stored at consecutive addresses from zero on; `@` followed by a hexadecimal address
+/// moves to that address, and addresses skipped over hold zero. Underscores within a word are
+/// ignored, and `//` starts a comment running to the end of the line. Block comments and `x` or
+/// `z` digits are not supported.
+pub fn parse_hex(text: &str) -> Result<Vec<u64>> {
+    let mut words = Vec::new();
+    let mut address = 0;
+    for (line, content) in text.lines().enumerate() {
+        let content = content.split("//").next().unwrap_or_default();
+        for word in content.split_whitespace() {
+            let invalid = || Error::InvalidHex {
+                line: line + 1,
+                word: word.to_string(),
+            };
+            let (target, digits) = match word.strip_prefix('@') {
+                Some(digits) => (true, digits),
+                None => (false, word),
+            };
+            let digits = digits.replace('_', "");
+            if target {
+                address = usize::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+                continue;
+            }
+            let value = u64::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+            if address >= 1 << MAX_ADDRESS_WIDTH {
+                return Err(Error::AddressOutOfRange {
+                    address,
+                    address_width: MAX_ADDRESS_WIDTH,
+                });
+            }
+            if address >= words.len() {
+                words.resize(address + 1, 0);
+            }
+            words[address] = value;
+            address += 1;
+        }
+    }
+    Ok(words)
+}
+
+pub fn read_hex_file(path: impl AsRef<Path>) -> Result<Vec<u64>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|error| Error::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    parse_hex(&text)
+}
+
+pub fn read_binary_file(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    std::fs::read(path).map_err(|error| Error::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+fn check_address_width(width: usize) -> Result<()> {
+    if width > MAX_ADDRESS_WIDTH {
+        return Err(Error::BusTooWide {
+            name: "address".to_string(),
+            width,
+            max: MAX_ADDRESS_WIDTH,
+        });
+    }
+    Ok(())
+}
+
+fn check_addresses(word_count: usize, address_width: usize) -> Result<()> {
+    if word_count > 1 << address_width {
+        return Err(Error::AddressOutOfRange {
+            address: word_count - 1,
+            address_width,
+        });
+    }
+    Ok(())
+}
+
+fn check_words(width: usize, words: &[u64]) -> Result<()> {
+    for (address, word) in words.iter().enumerate() {
+        if width < 64 && word >> width != 0 {
+            return Err(Error::ValueOutOfRange {
+                name: format!("word {address}"),
+                value: *word,
+                width,
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use crate::bus::Bus;
+use crate::emulator::{bit_name, Error, Result};
+use crate::netlist::{Netlist, Node, NodeId};
+
+/// Whether [`Emulator::from_module`] keeps the names of internal signals for probing.
+///
+/// [`Emulator::from_module`]: crate::emulator::Emulator::from_module
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hierarchy {
+    /// Compiles only what the outputs need, so internal signals may be optimised away.
+    Flatten,
+    /// Keeps every named signal observable.
+    Keep,
+}
+
+/// A reusable circuit definition with named input and output ports.
+///
+/// The netlist of a module is built like any other; instantiating the module copies it into
+/// another netlist, with the inputs connected to the buses supplied for them.
+#[derive(Clone, Debug)]
+pub struct Module {
+    name: String,
+    netlist: Netlist,
+    inputs: Vec<(String, Bus)>,
+    outputs: Vec<(String, Bus)>,
+}
+
+impl Module {
+    /// Creates a module with input ports of the given widths, which occupy the netlist's inputs
+    /// in order.
+    pub fn new(
+        name: impl Into<String>,
+        inputs: impl IntoIterator<Item = (impl Into<String>, usize)>,
+    ) -> Result<Self> {
+        let mut module = Self {
+            name: name.into(),
+            netlist: Netlist::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        let mut first = 0;
+        for (name, width) in inputs {
+            let name = name.into();
+            if module.inputs.iter().any(|(input, _)| *input == name) {
+                return Err(Error::DuplicateInput { name });
+            }
+            let bus = module.netlist.input_bus(first, width);
+            first += width;
+            name_bus(&mut module.netlist, &name, &bus)?;
+            module.inputs.push((name, bus));
+        }
+        Ok(module)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    pub fn netlist_mut(&mut self) -> &mut Netlist {
+        &mut self.netlist
+    }
+
+    pub fn inputs(&self) -> &[(String, Bus)] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[(String, Bus)] {
+        &self.outputs
+    }
+
+    pub fn input(&self, name: &str) -> Result<&Bus> {
+        find_port(&self.inputs, name).ok_or_else(|| Error::UnknownInput {
+            name: name.to_string(),
+        })
+    }
+
+    /// Adds an output port; its bits are named after the port, so the name must not be taken by
+    /// another port or signal.
+    pub fn set_output(&mut self, name: impl Into<String>, bus: impl Into<Bus>) -> Result<()> {
+        let name = name.into();
+        let bus = bus.into();
+        let taken = |(bit, node): (usize, &NodeId)| {
+            let named = self.netlist.named(&bit_name(&name, bus.width(), bit));
+            named.is_some_and(|named| named != *node)
+        };
+        if find_port(&self.inputs, &name).is_some()
+            || find_port(&self.outputs, &name).is_some()
+            || bus.bits().iter().enumerate().any(taken)
+        {
+            return Err(Error::DuplicateSignal { name });
+        }
+        name_bus(&mut self.netlist, &name, &bus)?;
+        self.outputs.push((name, bus));
+        Ok(())
+    }
+}
+
+/// The ports of a module instantiated with [`Netlist::instantiate`].
+#[derive(Clone, Debug)]
+pub struct Instance {
+    name: String,
+    outputs: Vec<(String, Bus)>,
+}
+
+impl Instance {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn outputs(&self) -> &[(String, Bus)] {
+        &self.outputs
+    }
+
+    pub fn output(&self, name: &str) -> Result<&Bus> {
+        find_port(&self.outputs, name).ok_or_else(|| Error::UnknownSignal {
+            name: format!("{}.{name}", self.name),
+        })
+    }
+}
+
+impl Netlist {
+    /// Copies `module` into this netlist with every input port connected to the bus supplied for
+    /// it by name.
+    ///
+    /// Every named signal of the module, including its ports, is named `instance.signal` here, so
+    /// no signal of this netlist may already be named `instance.` followed by anything.
+    pub fn instantiate(
+        &mut self,
+        module: &Module,
+        instance: &str,
+        inputs: impl IntoIterator<Item = (impl Into<String>, Bus)>,
+    ) -> Result<Instance> {
+        let input_count = module.inputs.iter().map(|(_, bus)| bus.width()).sum();
+        let mut connections = vec![None; input_count];
+        let mut bound = Vec::<String>::new();
+        for (name, bus) in inputs {
+            let name = name.into();
+            let port = module.input(&name)?;
+            if bound.contains(&name) {
+                return Err(Error::DuplicateInput { name });
+            }
+            if port.width() != bus.width() {
+                return Err(Error::PortWidthMismatch {
+                    name,
+                    supplied: bus.width(),
+                    expected: port.width(),
+                });
+            }
+            for (input, node) in port.bits().iter().zip(bus.bits()) {
+                assert!(
+                    node.index() < self.len(),
+                    "Node {} does not belong to this netlist",
+                    node.index()
+                );
+                let Node::Input { index } = module.netlist.node(*input) else {
+                    unreachable!("Input ports only contain inputs")
+                };
+                connections[*index] = Some(*node);
+            }
+            bound.push(name);
+        }
+        if let Some((name, _)) = module.inputs.iter().find(|(name, _)| !bound.contains(name)) {
+            return Err(Error::MissingInput { name: name.clone() });
+        }
+        module.netlist.check_bounds(input_count)?;
+        let prefix = format!("{instance}.");
+        if self.has_name_with_prefix(&prefix) {
+            return Err(Error::DuplicateInstance {
+                name: instance.to_string(),
+            });
+        }
+        let inputs = connections
+            .into_iter()
+            .map(Option::unwrap)
+            .collect::<Vec<_>>();
+        let ids = self.import(&module.netlist, &inputs);
+        for (name, node) in module.netlist.names() {
+            self.set_name(ids[node.index()], format!("{instance}.{name}"))?;
+        }
+        let outputs = module
+            .outputs
+            .iter()
+            .map(|(name, bus)| {
+                let bits = bus.bits().iter().map(|bit| ids[bit.index()]);
+                (name.clone(), Bus::new(bits))
+            })
+            .collect();
+        Ok(Instance {
+            name: instance.to_string(),
+            outputs,
+        })
+    }
+}
+
+fn find_port<'a>(ports: &'a [(String, Bus)], name: &str) -> Option<&'a Bus> {
+    ports
+        .iter()
+        .find(|(port, _)| port == name)
+        .map(|(_, bus)| bus)
+}
+
+fn name_bus(netlist: &mut Netlist, name: &str, bus: &Bus) -> Result<()> {
+    for (bit, node) in bus.bits().iter().enumerate() {
+        netlist.set_name(*node, bit_name(name, bus.width(), bit))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,600 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::emulator::{Component, Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Node {
+    Input {
+        index: usize,
+    },
+    /// The current value of state bit `index`, see [`Netlist::state`].
+    State {
+        index: usize,
+    },
+    /// The value of whatever drives wire `index`, see [`Netlist::wire`].
+    Wire {
+        index: usize,
+    },
+    Const(bool),
+    Not(NodeId),
+    Or(Vec<NodeId>),
+    And(Vec<NodeId>),
+    /// True if exactly one input is true.
+    OneHot(Vec<NodeId>),
+    /// True if an odd number of inputs is true.
+    Parity(Vec<NodeId>),
+    /// True if at least `k` inputs are true.
+    Threshold {
+        k: usize,
+        inputs: Vec<NodeId>,
+    },
+}
+
+impl Node {
+    pub fn operands(&self) -> &[NodeId] {
+        match self {
+            Node::Input { .. } | Node::State { .. } | Node::Wire { .. } | Node::Const(_) => &[],
+            Node::Not(input) => std::slice::from_ref(input),
+            Node::Or(inputs)
+            | Node::And(inputs)
+            | Node::OneHot(inputs)
+            | Node::Parity(inputs)
+            | Node::Threshold { inputs, .. } => inputs,
+        }
+    }
+}
+
+/// A circuit in which gates refer to their inputs by [`NodeId`].
+///
+/// Gates can only refer to nodes that were added before them. Structurally identical nodes are
+/// only stored once.
+///
+/// Sequential circuits keep state bits between clock cycles. Each state bit is a node whose value
+/// is replaced by the value of its next-state node on every clock edge.
+///
+/// Wires are nodes whose driver is connected later, so they can express feedback loops. A loop is
+/// only accepted if it passes through a wire created with [`feedback_wire`](Self::feedback_wire);
+/// such loops are evaluated until they settle.
+///
+/// Nodes can be given names, which are prefixed with the instance name when a
+/// [`Module`](crate::module::Module) is instantiated, so internal signals keep hierarchical names
+/// like `adder.carry`.
+#[derive(Clone, Debug, Default)]
+pub struct Netlist {
+    nodes: Vec<Node>,
+    lookup: HashMap<Node, NodeId>,
+    next_states: Vec<NodeId>,
+    wires: Vec<Wire>,
+    names: BTreeMap<String, NodeId>,
+}
+
+#[derive(Clone, Debug)]
+struct Wire {
+    driver: Option<NodeId>,
+    feedback: bool,
+}
+
+impl Netlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    fn add(&mut self, node: Node) -> NodeId {
+        if let Some(id) = self.lookup.get(&node) {
+            return *id;
+        }
+        for operand in node.operands() {
+            assert!(
+                operand.0 < self.nodes.len(),
+                "Node {} does not belong to this netlist",
+                operand.0
+            );
+        }
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node.clone());
+        self.lookup.insert(node, id);
+        id
+    }
+
+    pub fn input(&mut self, index: usize) -> NodeId {
+        self.add(Node::Input { index })
+    }
+
+    /// Adds a wire, which must be connected with [`drive`](Self::drive) before emulation.
+    ///
+    /// Loops through plain wires are rejected as combinational loops.
+    pub fn wire(&mut self) -> NodeId {
+        self.add_wire(false)
+    }
+
+    /// Adds a wire that is allowed to close a feedback loop, like the cross-coupled gates of a
+    /// latch.
+    pub fn feedback_wire(&mut self) -> NodeId {
+        self.add_wire(true)
+    }
+
+    fn add_wire(&mut self, feedback: bool) -> NodeId {
+        let index = self.wires.len();
+        self.wires.push(Wire {
+            driver: None,
+            feedback,
+        });
+        self.add(Node::Wire { index })
+    }
+
+    pub fn drive(&mut self, wire: NodeId, driver: NodeId) {
+        let Node::Wire { index } = self.nodes[wire.0] else {
+            panic!("Node {} is not a wire", wire.0)
+        };
+        assert!(
+            driver.0 < self.nodes.len(),
+            "Node {} does not belong to this netlist",
+            driver.0
+        );
+        assert!(
+            self.wires[index].driver.replace(driver).is_none(),
+            "Wire {} is already driven",
+            wire.0
+        );
+    }
+
+    pub fn driver(&self, wire: NodeId) -> Option<NodeId> {
+        match self.nodes[wire.0] {
+            Node::Wire { index } => self.wires[index].driver,
+            _ => None,
+        }
+    }
+
+    pub fn is_feedback_wire(&self, wire: NodeId) -> bool {
+        match self.nodes[wire.0] {
+            Node::Wire { index } => self.wires[index].feedback,
+            _ => false,
+        }
+    }
+
+    /// Returns the nodes whose values `id` is computed from within the same clock cycle, ignoring
+    /// feedback wires.
+    pub(crate) fn fanin(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let driver = match self.nodes[id.0] {
+            Node::Wire { index } if !self.wires[index].feedback => self.wires[index].driver,
+            _ => None,
+        };
+        self.nodes[id.0].operands().iter().copied().chain(driver)
+    }
+
+    /// Finds every combinational loop that does not pass through a feedback wire.
+    ///
+    /// Each loop is reported as the set of nodes in its strongly connected component.
+    pub fn combinational_loops(&self) -> Vec<Vec<NodeId>> {
+        // Iterative Tarjan, so large netlists cannot overflow the stack.
+        const UNVISITED: usize = usize::MAX;
+        let mut index = vec![UNVISITED; self.nodes.len()];
+        let mut low_link = vec![0; self.nodes.len()];
+        let mut on_stack = vec![false; self.nodes.len()];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        let mut loops = Vec::new();
+        for root in 0..self.nodes.len() {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            let mut work = vec![(
+                NodeId(root),
+                self.fanin(NodeId(root)).collect::<Vec<_>>(),
+                0,
+            )];
+            index[root] = next_index;
+            low_link[root] = next_index;
+            next_index += 1;
+            stack.push(NodeId(root));
+            on_stack[root] = true;
+            while let Some((node, fanin, position)) = work.last_mut() {
+                if let Some(next) = fanin.get(*position).copied() {
+                    *position += 1;
+                    if index[next.0] == UNVISITED {
+                        index[next.0] = next_index;
+                        low_link[next.0] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next.0] = true;
+                        let fanin = self.fanin(next).collect();
+                        work.push((next, fanin, 0));
+                    } else if on_stack[next.0] {
+                        low_link[node.0] = low_link[node.0].min(index[next.0]);
+                    }
+                    continue;
+                }
+                let node = *node;
+                work.pop();
+                if let Some((parent, _, _)) = work.last() {
+                    low_link[parent.0] = low_link[parent.0].min(low_link[node.0]);
+                }
+                if low_link[node.0] != index[node.0] {
+                    continue;
+                }
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member.0] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                let self_loop = self.fanin(node).any(|fanin| fanin == node);
+                if component.len() > 1 || self_loop {
+                    component.sort();
+                    loops.push(component);
+                }
+            }
+        }
+        loops
+    }
+
+    /// Names `node`; a node can have several names, but a name refers to a single node.
+    pub fn set_name(&mut self, node: NodeId, name: impl Into<String>) -> Result<()> {
+        let name = name.into();
+        assert!(
+            node.0 < self.nodes.len(),
+            "Node {} does not belong to this netlist",
+            node.0
+        );
+        match self.names.get(&name) {
+            Some(previous) if *previous != node => Err(Error::DuplicateSignal { name }),
+            _ => {
+                self.names.insert(name, node);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn named(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns every named node, ordered by name.
+    pub fn names(&self) -> impl Iterator<Item = (&str, NodeId)> {
+        self.names.iter().map(|(name, node)| (name.as_str(), *node))
+    }
+
+    /// True if any name starts with `prefix`.
+    pub(crate) fn has_name_with_prefix(&self, prefix: &str) -> bool {
+        self.names
+            .range(prefix.to_string()..)
+            .next()
+            .is_some_and(|(name, _)| name.starts_with(prefix))
+    }
+
+    /// Copies every node of `other` into this netlist, with input `i` of `other` replaced by
+    /// `inputs[i]`, and returns the new id of every node of `other`.
+    ///
+    /// State bits and wires are copied as new state bits and wires; names are not copied.
+    pub(crate) fn import(&mut self, other: &Netlist, inputs: &[NodeId]) -> Vec<NodeId> {
+        let mut ids = Vec::<NodeId>::with_capacity(other.len());
+        let mut states = Vec::new();
+        let mut wires = Vec::new();
+        for node in &other.nodes {
+            let map = |ids: &[NodeId], inputs: &[NodeId]| {
+                inputs.iter().map(|input| ids[input.0]).collect::<Vec<_>>()
+            };
+            let id = match node {
+                Node::Input { index } => inputs[*index],
+                Node::State { index } => {
+                    let state = self.state();
+                    states.push((state, other.next_states[*index]));
+                    state
+                }
+                Node::Wire { index } => {
+                    let wire = self.add_wire(other.wires[*index].feedback);
+                    wires.push((wire, other.wires[*index].driver));
+                    wire
+                }
+                Node::Const(value) => self.constant(*value),
+                Node::Not(input) => self.not(ids[input.0]),
+                Node::Or(inputs) => self.add(Node::Or(map(&ids, inputs))),
+                Node::And(inputs) => self.add(Node::And(map(&ids, inputs))),
+                Node::OneHot(inputs) => self.add(Node::OneHot(map(&ids, inputs))),
+                Node::Parity(inputs) => self.add(Node::Parity(map(&ids, inputs))),
+                Node::Threshold { k, inputs } => self.add(Node::Threshold {
+                    k: *k,
+                    inputs: map(&ids, inputs),
+                }),
+            };
+            ids.push(id);
+        }
+        for (state, next) in states {
+            self.set_next(state, ids[next.0]);
+        }
+        for (wire, driver) in wires {
+            if let Some(driver) = driver {
+                self.drive(wire, ids[driver.0]);
+            }
+        }
+        ids
+    }
+
+    /// Adds a new state bit, which holds its value until [`set_next`](Self::set_next) is called.
+    pub fn state(&mut self) -> NodeId {
+        let index = self.next_states.len();
+        let id = self.add(Node::State { index });
+        self.next_states.push(id);
+        id
+    }
+
+    /// Sets the value `state` takes on the next clock edge.
+    pub fn set_next(&mut self, state: NodeId, next: NodeId) {
+        let Node::State { index } = self.nodes[state.0] else {
+            panic!("Node {} is not a state bit", state.0)
+        };
+        assert!(
+            next.0 < self.nodes.len(),
+            "Node {} does not belong to this netlist",
+            next.0
+        );
+        self.next_states[index] = next;
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.next_states.len()
+    }
+
+    /// Returns the next-state node of every state bit, by state index.
+    pub fn next_states(&self) -> &[NodeId] {
+        &self.next_states
+    }
+
+    /// Makes `state` a D flip-flop storing `d` on every clock edge.
+    pub fn d_flip_flop(&mut self, state: NodeId, d: NodeId) {
+        self.set_next(state, d);
+    }
+
+    /// Makes `state` a clocked SR latch; `set` wins if both inputs are high.
+    pub fn sr_latch(&mut self, state: NodeId, set: NodeId, reset: NodeId) {
+        let not_reset = self.not(reset);
+        let hold = self.and([state, not_reset]);
+        let next = self.or([set, hold]);
+        self.set_next(state, next);
+    }
+
+    /// Makes `state` a JK flip-flop, which toggles if both inputs are high.
+    pub fn jk_flip_flop(&mut self, state: NodeId, j: NodeId, k: NodeId) {
+        let not_state = self.not(state);
+        let not_k = self.not(k);
+        let set = self.and([j, not_state]);
+        let hold = self.and([not_k, state]);
+        let next = self.or([set, hold]);
+        self.set_next(state, next);
+    }
+
+    /// Makes `state` a register bit loading `d` when `enable` is high; `reset` clears it and takes
+    /// precedence over `enable`.
+    pub fn register(&mut self, state: NodeId, d: NodeId, enable: NodeId, reset: NodeId) {
+        let not_enable = self.not(enable);
+        let load = self.and([enable, d]);
+        let hold = self.and([not_enable, state]);
+        let value = self.or([load, hold]);
+        let not_reset = self.not(reset);
+        let next = self.and([not_reset, value]);
+        self.set_next(state, next);
+    }
+
+    pub fn not(&mut self, input: NodeId) -> NodeId {
+        self.add(Node::Not(input))
+    }
+
+    pub fn or(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        assert!(inputs.len() > 1, "Or gate requires at least two inputs");
+        self.add(Node::Or(inputs))
+    }
+
+    pub fn and(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        assert!(inputs.len() > 1, "And gate requires at least two inputs");
+        self.add(Node::And(inputs))
+    }
+
+    #[deprecated(note = "use `parity` or `one_hot`, which differ for more than two inputs")]
+    pub fn xor(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        self.one_hot(inputs)
+    }
+
+    pub fn one_hot(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        assert!(
+            inputs.len() > 1,
+            "One-hot gate requires at least two inputs"
+        );
+        self.add(Node::OneHot(inputs))
+    }
+
+    pub fn parity(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        assert!(inputs.len() > 1, "Parity gate requires at least two inputs");
+        self.add(Node::Parity(inputs))
+    }
+
+    /// Adds a gate that is true if at least `k` inputs are; `k = 1` is an or and `k = n` an and,
+    /// which are added as such.
+    pub fn threshold(&mut self, k: usize, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        assert!(
+            inputs.len() > 1,
+            "Threshold gate requires at least two inputs"
+        );
+        assert!(
+            (1..=inputs.len()).contains(&k),
+            "Threshold gate requires a threshold between 1 and its input count"
+        );
+        if k == 1 {
+            self.or(inputs)
+        } else if k == inputs.len() {
+            self.and(inputs)
+        } else {
+            self.add(Node::Threshold { k, inputs })
+        }
+    }
+
+    /// Adds a gate that is true if more than half of its inputs are.
+    pub fn majority(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        self.threshold(inputs.len() / 2 + 1, inputs)
+    }
+
+    pub fn nand(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let and = self.and(inputs);
+        self.not(and)
+    }
+
+    pub fn nor(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let or = self.or(inputs);
+        self.not(or)
+    }
+
+    pub fn xnor(&mut self, inputs: impl IntoIterator<Item = NodeId>) -> NodeId {
+        let parity = self.parity(inputs);
+        self.not(parity)
+    }
+
+    pub fn constant(&mut self, value: bool) -> NodeId {
+        self.add(Node::Const(value))
+    }
+
+    /// Returns one node per value of `select`, true when `select`, most significant bit first,
+    /// equals its index.
+    pub fn decoder(&mut self, select: &[NodeId]) -> Vec<NodeId> {
+        assert!(
+            !select.is_empty(),
+            "Decoder requires at least one select line"
+        );
+        let mut minterms = vec![None];
+        for bit in select {
+            let not_bit = self.not(*bit);
+            minterms = minterms
+                .into_iter()
+                .flat_map(|minterm| [(minterm, not_bit), (minterm, *bit)])
+                .map(|(minterm, bit)| match minterm {
+                    Some(minterm) => Some(self.and([minterm, bit])),
+                    None => Some(bit),
+                })
+                .collect();
+        }
+        minterms.into_iter().flatten().collect()
+    }
+
+    /// Selects `inputs[select]`; there must be one input per value of `select`.
+    pub fn mux(&mut self, select: &[NodeId], inputs: &[NodeId]) -> NodeId {
+        assert_eq!(
+            inputs.len(),
+            1 << select.len(),
+            "Multiplexer requires one input per value of its select lines"
+        );
+        let terms = self
+            .decoder(select)
+            .into_iter()
+            .zip(inputs)
+            .map(|(minterm, input)| self.and([minterm, *input]))
+            .collect::<Vec<_>>();
+        self.or(terms)
+    }
+
+    /// Routes `input` to output `select`; every other output is false.
+    pub fn demux(&mut self, select: &[NodeId], input: NodeId) -> Vec<NodeId> {
+        self.decoder(select)
+            .into_iter()
+            .map(|minterm| self.and([minterm, input]))
+            .collect()
+    }
+
+    /// Returns the index of the last true input, most significant bit first, followed by a node
+    /// that is true if any input is.
+    pub fn priority_encoder(&mut self, inputs: &[NodeId]) -> Vec<NodeId> {
+        assert!(
+            inputs.len() > 1,
+            "Priority encoder requires at least two inputs"
+        );
+        let width = priority_encoder_width(inputs.len());
+        // `higher` is true if any input after the current one is.
+        let mut higher = inputs[inputs.len() - 1];
+        let mut active = vec![higher];
+        for input in inputs[..inputs.len() - 1].iter().rev() {
+            let not_higher = self.not(higher);
+            active.push(self.and([*input, not_higher]));
+            higher = self.or([*input, higher]);
+        }
+        active.reverse();
+        let mut outputs = (0..width)
+            .rev()
+            .map(|bit| {
+                let terms = (0..inputs.len())
+                    .filter(|i| i >> bit & 1 != 0)
+                    .map(|i| active[i])
+                    .collect::<Vec<_>>();
+                self.any(&terms)
+            })
+            .collect::<Vec<_>>();
+        outputs.push(higher);
+        outputs
+    }
+
+    /// Ors any number of nodes, adding no gate for a single node.
+    pub(crate) fn any(&mut self, nodes: &[NodeId]) -> NodeId {
+        match nodes {
+            [] => self.constant(false),
+            [node] => *node,
+            _ => self.or(nodes.iter().copied()),
+        }
+    }
+
+    /// Adds the gates of a component tree, reusing nodes that already exist.
+    pub fn lower(&mut self, component: &Component) -> NodeId {
+        component.lower(self)
+    }
+
+    pub fn check_bounds(&self, input_count: usize) -> Result<()> {
+        for node in &self.nodes {
+            if let Node::Input { index } = node {
+                if *index >= input_count {
+                    return Err(Error::InputOutOfBounds {
+                        index: *index,
+                        input_count,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Number of address bits of a priority encoder with `input_count` inputs.
+pub(crate) fn priority_encoder_width(input_count: usize) -> usize {
+    (usize::BITS - (input_count - 1).leading_zeros()) as usize
+}
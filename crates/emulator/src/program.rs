@@ -0,0 +1,317 @@
+use crate::emulator::{Error, Result};
+use crate::netlist::{Netlist, Node, NodeId};
+use crate::value::Value;
+
+pub type Register = u32;
+
+/// A single step of a [`Program`]; instruction `i` writes register `i`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Input {
+        index: usize,
+    },
+    State {
+        index: usize,
+    },
+    Const {
+        value: bool,
+    },
+    /// Reads the value of feedback wire `index` from the previous settling iteration.
+    Feedback {
+        index: usize,
+    },
+    Not {
+        input: Register,
+    },
+    Or2 {
+        a: Register,
+        b: Register,
+    },
+    And2 {
+        a: Register,
+        b: Register,
+    },
+    Xor2 {
+        a: Register,
+        b: Register,
+    },
+    /// Reads the registers listed in `operands[start..end]`, as do `And`, `OneHot` and `Parity`.
+    Or {
+        start: u32,
+        end: u32,
+    },
+    And {
+        start: u32,
+        end: u32,
+    },
+    OneHot {
+        start: u32,
+        end: u32,
+    },
+    Parity {
+        start: u32,
+        end: u32,
+    },
+    Threshold {
+        k: u32,
+        start: u32,
+        end: u32,
+    },
+    /// A threshold of two out of three, as in the carry of a full adder.
+    Majority3 {
+        a: Register,
+        b: Register,
+        c: Register,
+    },
+}
+
+/// A netlist flattened into a linear list of instructions over a register file.
+///
+/// Only the nodes that the outputs depend on are compiled. Feedback wires are read from a separate
+/// list of values, and [`settle`](Self::settle) reruns the program until they stop changing.
+#[derive(Clone, Debug)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+    operands: Vec<Register>,
+    outputs: Vec<Register>,
+    probes: Vec<Register>,
+    next_states: Vec<Register>,
+    feedback: Vec<Register>,
+    feedback_nodes: Vec<NodeId>,
+}
+
+impl Program {
+    /// Compiles the nodes needed for `outputs` and the next value of every state bit and feedback
+    /// wire, in topological order.
+    pub fn compile(netlist: &Netlist, outputs: &[NodeId]) -> Result<Self> {
+        Self::compile_with_probes(netlist, outputs, &[])
+    }
+
+    /// Like [`compile`](Self::compile), but also keeps the values of `probes` observable through
+    /// [`probe_registers`](Self::probe_registers).
+    pub fn compile_with_probes(
+        netlist: &Netlist,
+        outputs: &[NodeId],
+        probes: &[NodeId],
+    ) -> Result<Self> {
+        if let Some(nodes) = netlist.combinational_loops().into_iter().next() {
+            return Err(Error::CombinationalLoop { nodes });
+        }
+        let mut feedback = vec![None; netlist.len()];
+        let mut feedback_nodes = Vec::new();
+        let mut roots = outputs.to_vec();
+        roots.extend(probes);
+        roots.extend(netlist.next_states());
+        for id in netlist.ids() {
+            if netlist.is_feedback_wire(id) {
+                let driver = netlist.driver(id).ok_or(Error::UndrivenWire { node: id })?;
+                feedback[id.index()] = Some(feedback_nodes.len());
+                feedback_nodes.push(id);
+                roots.push(driver);
+            }
+        }
+        let mut program = Self {
+            instructions: Vec::new(),
+            operands: Vec::new(),
+            outputs: Vec::new(),
+            probes: Vec::new(),
+            next_states: Vec::new(),
+            feedback: Vec::new(),
+            feedback_nodes,
+        };
+        let mut registers = vec![None; netlist.len()];
+        let mut stack = roots
+            .iter()
+            .rev()
+            .map(|root| (*root, false))
+            .collect::<Vec<_>>();
+        while let Some((id, expanded)) = stack.pop() {
+            if registers[id.index()].is_some() {
+                continue;
+            }
+            if !expanded {
+                stack.push((id, true));
+                if let Node::Wire { .. } = netlist.node(id) {
+                    if feedback[id.index()].is_none() && netlist.driver(id).is_none() {
+                        return Err(Error::UndrivenWire { node: id });
+                    }
+                }
+                for fanin in netlist.fanin(id) {
+                    if registers[fanin.index()].is_none() {
+                        stack.push((fanin, false));
+                    }
+                }
+                continue;
+            }
+            let register = |id: &NodeId| registers[id.index()].unwrap();
+            let instruction = match netlist.node(id) {
+                Node::Input { index } => Instruction::Input { index: *index },
+                Node::State { index } => Instruction::State { index: *index },
+                Node::Const(value) => Instruction::Const { value: *value },
+                Node::Wire { .. } => match feedback[id.index()] {
+                    Some(index) => Instruction::Feedback { index },
+                    None => {
+                        registers[id.index()] = Some(register(&netlist.driver(id).unwrap()));
+                        continue;
+                    }
+                },
+                Node::Not(input) => Instruction::Not {
+                    input: register(input),
+                },
+                Node::Or(inputs) if inputs.len() == 2 => Instruction::Or2 {
+                    a: register(&inputs[0]),
+                    b: register(&inputs[1]),
+                },
+                Node::And(inputs) if inputs.len() == 2 => Instruction::And2 {
+                    a: register(&inputs[0]),
+                    b: register(&inputs[1]),
+                },
+                Node::OneHot(inputs) | Node::Parity(inputs) if inputs.len() == 2 => {
+                    Instruction::Xor2 {
+                        a: register(&inputs[0]),
+                        b: register(&inputs[1]),
+                    }
+                }
+                node @ (Node::Or(inputs)
+                | Node::And(inputs)
+                | Node::OneHot(inputs)
+                | Node::Parity(inputs)) => {
+                    let start = program.operands.len() as u32;
+                    program.operands.extend(inputs.iter().map(register));
+                    let end = program.operands.len() as u32;
+                    match node {
+                        Node::Or(_) => Instruction::Or { start, end },
+                        Node::And(_) => Instruction::And { start, end },
+                        Node::OneHot(_) => Instruction::OneHot { start, end },
+                        _ => Instruction::Parity { start, end },
+                    }
+                }
+                Node::Threshold { k: 2, inputs } if inputs.len() == 3 => Instruction::Majority3 {
+                    a: register(&inputs[0]),
+                    b: register(&inputs[1]),
+                    c: register(&inputs[2]),
+                },
+                Node::Threshold { k, inputs } => {
+                    let start = program.operands.len() as u32;
+                    program.operands.extend(inputs.iter().map(register));
+                    let end = program.operands.len() as u32;
+                    Instruction::Threshold {
+                        k: *k as u32,
+                        start,
+                        end,
+                    }
+                }
+            };
+            registers[id.index()] = Some(program.instructions.len() as Register);
+            program.instructions.push(instruction);
+        }
+        let register = |id: &NodeId| registers[id.index()].unwrap();
+        program.outputs = outputs.iter().map(register).collect();
+        program.probes = probes.iter().map(register).collect();
+        program.next_states = netlist.next_states().iter().map(register).collect();
+        program.feedback = program
+            .feedback_nodes
+            .iter()
+            .map(|wire| register(&netlist.driver(*wire).unwrap()))
+            .collect();
+        Ok(program)
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn register_count(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn output_registers(&self) -> &[Register] {
+        &self.outputs
+    }
+
+    pub fn probe_registers(&self) -> &[Register] {
+        &self.probes
+    }
+
+    /// Returns the register holding the next value of every state bit, by state index.
+    pub fn next_state_registers(&self) -> &[Register] {
+        &self.next_states
+    }
+
+    pub fn feedback_count(&self) -> usize {
+        self.feedback.len()
+    }
+
+    fn operands(&self, start: u32, end: u32) -> &[Register] {
+        &self.operands[start as usize..end as usize]
+    }
+
+    /// Runs the program until the feedback wires are stable, starting from the values in
+    /// `feedback` and leaving the settled values there.
+    pub fn settle<V: Value>(
+        &self,
+        inputs: &[V],
+        state: &[V],
+        feedback: &mut [V],
+        registers: &mut Vec<V>,
+        iteration_limit: usize,
+    ) -> Result<()> {
+        let mut changed = Vec::new();
+        for _ in 0..iteration_limit.max(1) {
+            self.run(inputs, state, feedback, registers);
+            changed.clear();
+            for (index, register) in self.feedback.iter().enumerate() {
+                let value = registers[*register as usize];
+                if feedback[index] != value {
+                    feedback[index] = value;
+                    changed.push(self.feedback_nodes[index]);
+                }
+            }
+            if changed.is_empty() {
+                return Ok(());
+            }
+        }
+        Err(Error::Unstable {
+            nodes: changed,
+            iterations: iteration_limit,
+        })
+    }
+
+    /// Runs the program once, leaving the value of every instruction in `registers`.
+    pub fn run<V: Value>(&self, inputs: &[V], state: &[V], feedback: &[V], registers: &mut Vec<V>) {
+        registers.clear();
+        registers.reserve(self.instructions.len());
+        for instruction in &self.instructions {
+            let r = |register: &Register| registers[*register as usize];
+            let value = match *instruction {
+                Instruction::Input { index } => inputs[index],
+                Instruction::State { index } => state[index],
+                Instruction::Const { value } => V::from_bool(value),
+                Instruction::Feedback { index } => feedback[index],
+                Instruction::Not { input } => r(&input).not(),
+                Instruction::Or2 { a, b } => r(&a).or(r(&b)),
+                Instruction::And2 { a, b } => r(&a).and(r(&b)),
+                Instruction::Xor2 { a, b } => r(&a).xor(r(&b)),
+                Instruction::Or { start, end } => self
+                    .operands(start, end)
+                    .iter()
+                    .fold(V::ZERO, |acc, o| acc.or(r(o))),
+                Instruction::And { start, end } => self
+                    .operands(start, end)
+                    .iter()
+                    .fold(V::ONE, |acc, o| acc.and(r(o))),
+                Instruction::Majority3 { a, b, c } => V::majority3(r(&a), r(&b), r(&c)),
+                Instruction::Threshold { k, start, end } => {
+                    V::threshold(k as usize, self.operands(start, end).iter().map(r))
+                }
+                Instruction::Parity { start, end } => {
+                    V::parity(self.operands(start, end).iter().map(r))
+                }
+                Instruction::OneHot { start, end } => {
+                    V::one_hot(self.operands(start, end).iter().map(r))
+                }
+            };
+            registers.push(value);
+        }
+    }
+}
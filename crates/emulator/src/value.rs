@@ -0,0 +1,225 @@
+use crate::logic::Logic;
+
+/// The algebra a circuit is evaluated in.
+///
+/// Implemented for `bool`, for `u64` words holding 64 independent patterns, for four-valued
+/// [`Logic`], and for `f64` signal probabilities.
+pub trait Value: Copy + PartialEq {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn not(self) -> Self;
+
+    fn and(self, other: Self) -> Self;
+
+    fn or(self, other: Self) -> Self;
+
+    fn xor(self, other: Self) -> Self;
+
+    /// Returns one if an odd number of values is one.
+    fn parity(values: impl IntoIterator<Item = Self>) -> Self {
+        values.into_iter().fold(Self::ZERO, Self::xor)
+    }
+
+    /// Returns one if exactly one value is one.
+    fn one_hot(values: impl IntoIterator<Item = Self>) -> Self {
+        let mut once = Self::ZERO;
+        let mut twice = Self::ZERO;
+        for value in values {
+            twice = twice.or(once.and(value));
+            once = once.or(value);
+        }
+        once.and(twice.not())
+    }
+
+    /// Returns one if at least `k` values are one.
+    fn threshold(k: usize, values: impl IntoIterator<Item = Self>) -> Self {
+        // `at_least[j]` is one if at least `j + 1` of the values so far are one.
+        let mut at_least = vec![Self::ZERO; k];
+        for value in values {
+            for j in (0..k).rev() {
+                let below = if j == 0 { Self::ONE } else { at_least[j - 1] };
+                at_least[j] = at_least[j].or(below.and(value));
+            }
+        }
+        at_least.last().copied().unwrap_or(Self::ONE)
+    }
+
+    /// Returns one if at least two of the three values are one.
+    fn majority3(a: Self, b: Self, c: Self) -> Self {
+        Self::threshold(2, [a, b, c])
+    }
+
+    fn from_bool(value: bool) -> Self {
+        if value {
+            Self::ONE
+        } else {
+            Self::ZERO
+        }
+    }
+}
+
+impl Value for bool {
+    const ZERO: Self = false;
+    const ONE: Self = true;
+
+    fn not(self) -> Self {
+        !self
+    }
+
+    fn and(self, other: Self) -> Self {
+        self & other
+    }
+
+    fn or(self, other: Self) -> Self {
+        self | other
+    }
+
+    fn xor(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    fn threshold(k: usize, values: impl IntoIterator<Item = Self>) -> Self {
+        values.into_iter().filter(|value| *value).count() >= k
+    }
+
+    fn majority3(a: Self, b: Self, c: Self) -> Self {
+        a & b | c & (a | b)
+    }
+}
+
+impl Value for u64 {
+    const ZERO: Self = 0;
+    const ONE: Self = !0;
+
+    fn not(self) -> Self {
+        !self
+    }
+
+    fn and(self, other: Self) -> Self {
+        self & other
+    }
+
+    fn or(self, other: Self) -> Self {
+        self | other
+    }
+
+    fn xor(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    fn threshold(k: usize, values: impl IntoIterator<Item = Self>) -> Self {
+        // Counts the ones in every lane with a bit-sliced counter: `count[i]` holds bit `i` of
+        // the count of each lane, and every value is added with a chain of half adders.
+        let mut count = [0u64; u64::BITS as usize];
+        let mut width = 0;
+        for value in values {
+            let mut carry = value;
+            for slice in &mut count[..width] {
+                if carry == 0 {
+                    break;
+                }
+                (*slice, carry) = (*slice ^ carry, *slice & carry);
+            }
+            if carry != 0 {
+                count[width] = carry;
+                width += 1;
+            }
+        }
+        if k.checked_shr(width as u32).unwrap_or(0) != 0 {
+            return 0;
+        }
+        // Compares the count of every lane with `k`, most significant bit first.
+        let mut greater = 0;
+        let mut equal = !0;
+        for (bit, slice) in count[..width].iter().enumerate().rev() {
+            let k_bit = if k >> bit & 1 != 0 { !0 } else { 0 };
+            greater |= equal & slice & !k_bit;
+            equal &= !(slice ^ k_bit);
+        }
+        greater | equal
+    }
+
+    fn majority3(a: Self, b: Self, c: Self) -> Self {
+        a & b | c & (a | b)
+    }
+}
+
+impl Value for Logic {
+    const ZERO: Self = Logic::Zero;
+    const ONE: Self = Logic::One;
+
+    fn not(self) -> Self {
+        !self
+    }
+
+    fn and(self, other: Self) -> Self {
+        self & other
+    }
+
+    fn or(self, other: Self) -> Self {
+        self | other
+    }
+
+    fn xor(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    fn one_hot(values: impl IntoIterator<Item = Self>) -> Self {
+        Logic::one_hot(values)
+    }
+}
+
+/// The probability of a signal being one, assuming the inputs of every gate are independent.
+///
+/// Signals that reconverge after fanning out are correlated, so their probabilities are only
+/// estimates.
+impl Value for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn not(self) -> Self {
+        1.0 - self
+    }
+
+    fn and(self, other: Self) -> Self {
+        self * other
+    }
+
+    fn or(self, other: Self) -> Self {
+        self + other - self * other
+    }
+
+    fn xor(self, other: Self) -> Self {
+        self + other - 2.0 * self * other
+    }
+
+    fn threshold(k: usize, values: impl IntoIterator<Item = Self>) -> Self {
+        if k == 0 {
+            return 1.0;
+        }
+        // `exactly[j]` is the probability of exactly `j` of the values so far being one, with
+        // `exactly[k]` covering `k` or more.
+        let mut exactly = vec![0.0; k + 1];
+        exactly[0] = 1.0;
+        for p in values {
+            exactly[k] += exactly[k - 1] * p;
+            for j in (1..k).rev() {
+                exactly[j] = exactly[j] * (1.0 - p) + exactly[j - 1] * p;
+            }
+            exactly[0] *= 1.0 - p;
+        }
+        exactly[k]
+    }
+
+    fn one_hot(values: impl IntoIterator<Item = Self>) -> Self {
+        // Probabilities of no input and of exactly one input being one so far.
+        let mut none = 1.0;
+        let mut one = 0.0;
+        for p in values {
+            one = one * (1.0 - p) + none * p;
+            none *= 1.0 - p;
+        }
+        one
+    }
+}
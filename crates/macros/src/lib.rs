@@ -1,24 +1,784 @@
 use proc_macro::token_stream::IntoIter;
-use proc_macro::{Spacing, TokenStream, TokenTree};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::collections::HashMap;
 use std::iter::Peekable;
+use std::path::Path;
 
+/// Builds an `Emulator` from a list of `name = expression;` statements.
+///
+/// Inputs are declared by name with `input a, b, c;`, in positional order.
+///
+/// Expressions are `input(index)`, a previously defined signal, a constant `0` or `1`, or one of
+/// the gates `not`, `buffer`, `or`, `and`, `nor`, `nand`, `xor`, `parity`, `one_hot`, `xnor`,
+/// `majority` and `threshold(k, ...)`. `xor` takes exactly two inputs; `parity` is true for an
+/// odd number of true inputs and `one_hot` for exactly one. `xnor` is the complement of `parity`.
+///
+/// Statements prefixed with `pub` are the outputs of the circuit; without any, the last statement
+/// is its only output.
+///
+/// A `table name;` or `table name(input_count);` directive evaluates the circuit while expanding
+/// the macro instead and emits `fn name(inputs: [bool; N]) -> bool` indexing a `const` truth table.
+/// With more than one output, the function returns `[bool; M]` instead.
 #[proc_macro]
 pub fn emulator(tokens: TokenStream) -> TokenStream {
+    match parse_circuit(tokens).and_then(|circuit| expand_circuit(&circuit)) {
+        Ok(expanded) => expanded.parse().unwrap(),
+        Err(error) => error.into_tokens(),
+    }
+}
+
+/// Expands a circuit description stored in a file like `emulator!`.
+///
+/// The path is relative to the directory of the crate's `Cargo.toml`. The file is included with
+/// `include_str!` as well, so the crate is rebuilt when it changes.
+#[proc_macro]
+pub fn include_circuit(tokens: TokenStream) -> TokenStream {
+    match include_circuit_file(tokens) {
+        Ok(expanded) => expanded.parse().unwrap(),
+        Err(error) => error.into_tokens(),
+    }
+}
+
+fn include_circuit_file(tokens: TokenStream) -> ParseResult<String> {
+    let mut tokens = tokens.into_iter();
+    let literal = match tokens.next() {
+        Some(TokenTree::Literal(literal)) => literal,
+        token => {
+            return Err(CompileError::new(
+                span_or(token.as_ref(), Span::call_site()),
+                "Expected the path of a circuit file",
+            ))
+        }
+    };
+    if let Some(token) = tokens.next() {
+        return Err(CompileError::new(
+            token.span(),
+            "Expected only the path of a circuit file",
+        ));
+    }
+    let literal_string = literal.to_string();
+    let Some(relative) = literal_string
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+        .filter(|path| !path.contains('\\'))
+    else {
+        return Err(CompileError::new(
+            literal.span(),
+            "The path of a circuit file must be a plain string literal",
+        ));
+    };
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = Path::new(&manifest_dir).join(relative);
+    let source = std::fs::read_to_string(&path).map_err(|error| {
+        CompileError::new(
+            literal.span(),
+            format!("Could not read `{}`: {error}", path.display()),
+        )
+    })?;
+    let circuit_tokens = source.parse().map_err(|error| {
+        CompileError::new(
+            literal.span(),
+            format!("Could not tokenize `{}`: {error}", path.display()),
+        )
+    })?;
+    let circuit = parse_circuit(circuit_tokens).map_err(|error| {
+        CompileError::new(
+            literal.span(),
+            format!("{} (in `{}`)", error.message, path.display()),
+        )
+    })?;
+    let expanded = expand_circuit(&circuit)?;
+    let track = format!(
+        "const _: &str = include_str!({:?});",
+        path.display().to_string()
+    );
+    if circuit.table.is_some() {
+        Ok(format!("{track} {expanded}"))
+    } else {
+        Ok(format!("{{ {track} {expanded} }}"))
+    }
+}
+
+fn parse_circuit(tokens: TokenStream) -> ParseResult<Circuit> {
     let mut tokens = tokens.into_iter().peekable();
+    let mut circuit = Circuit::default();
     while tokens.peek().is_some() {
-        parse_statement(&mut tokens);
+        parse_statement(&mut tokens, &mut circuit)?;
+    }
+    if circuit.statements.is_empty() {
+        return Err(CompileError::new(
+            Span::call_site(),
+            "A circuit requires at least one statement",
+        ));
+    }
+    Ok(circuit)
+}
+
+fn expand_circuit(circuit: &Circuit) -> ParseResult<String> {
+    match &circuit.table {
+        Some(table) => expand_table(table, circuit),
+        None => Ok(expand_emulator(circuit)),
+    }
+}
+
+fn expand_emulator(circuit: &Circuit) -> String {
+    let outputs = circuit
+        .outputs()
+        .into_iter()
+        .map(|output| format!("({:?}, __signal_{output})", circuit.names[output]))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut code = Vec::new();
+    for (i, statement) in circuit.statements.iter().enumerate() {
+        let node = statement.expand(&mut code);
+        code.push(format!("let __signal_{i} = {node};"));
+    }
+    let input_count = circuit.input_count();
+    let emulator = if circuit.inputs.is_empty() {
+        format!(
+            "::emulator::emulator::Emulator::from_netlist({input_count}, __netlist, [{outputs}])"
+        )
+    } else {
+        let names = (0..input_count)
+            .map(|i| match circuit.inputs.get(i) {
+                Some(name) => format!("{name:?}"),
+                None => format!("\"I{i}\""),
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "::emulator::emulator::Inputs::new([{names}]).and_then(|__inputs| {{
+                ::emulator::emulator::Emulator::from_netlist(__inputs, __netlist, [{outputs}])
+            }})",
+            names = names.join(", "),
+        )
+    };
+    format!(
+        "{{
+            let mut __netlist = ::emulator::netlist::Netlist::new();
+            {code}
+            {emulator}
+        }}",
+        code = code.join("\n"),
+    )
+}
+
+/// Largest input count for which `table` evaluates the circuit at compile time.
+const MAX_TABLE_INPUTS: usize = 16;
+
+fn expand_table(table: &Table, circuit: &Circuit) -> ParseResult<String> {
+    let input_count = match table.input_count {
+        Some((input_count, _)) => {
+            for statement in &circuit.statements {
+                statement.check_bounds(input_count)?;
+            }
+            input_count
+        }
+        None => circuit.input_count(),
+    };
+    if input_count > MAX_TABLE_INPUTS {
+        let span = table
+            .input_count
+            .map_or(table.name.span(), |(_, span)| span);
+        return Err(CompileError::new(
+            span,
+            format!("A truth table supports at most {MAX_TABLE_INPUTS} inputs, but the circuit has {input_count}"),
+        ));
+    }
+    let outputs = circuit.outputs();
+    let mut rows = Vec::with_capacity(1 << input_count);
+    let mut inputs = vec![false; input_count];
+    let mut values = Vec::with_capacity(circuit.statements.len());
+    for i in 0..1usize << input_count {
+        for bit_offset in 0..input_count {
+            inputs[input_count - bit_offset - 1] = (i >> bit_offset) & 1 != 0;
+        }
+        values.clear();
+        for statement in &circuit.statements {
+            let value = statement.evaluate(&inputs, &values);
+            values.push(value);
+        }
+        let row = outputs
+            .iter()
+            .map(|output| values[*output].to_string())
+            .collect::<Vec<_>>();
+        if outputs.len() == 1 {
+            rows.push(row.join(""));
+        } else {
+            rows.push(format!("[{}]", row.join(", ")));
+        }
+    }
+    let output_type = if outputs.len() == 1 {
+        "bool".to_string()
+    } else {
+        format!("[bool; {}]", outputs.len())
+    };
+    Ok(format!(
+        "fn {name}(inputs: [bool; {input_count}]) -> {output_type} {{
+            const TABLE: [{output_type}; {len}] = [{rows}];
+            let mut index = 0usize;
+            for input in inputs {{
+                index = index << 1 | input as usize;
+            }}
+            TABLE[index]
+        }}",
+        name = table.name,
+        len = rows.len(),
+        rows = rows.join(", "),
+    ))
+}
+
+#[derive(Default)]
+struct Circuit {
+    signals: HashMap<String, usize>,
+    inputs: Vec<String>,
+    names: Vec<String>,
+    statements: Vec<Expr>,
+    outputs: Vec<usize>,
+    table: Option<Table>,
+}
+
+impl Circuit {
+    /// Returns the statements marked with `pub`, or the last statement if none are.
+    fn outputs(&self) -> Vec<usize> {
+        if self.outputs.is_empty() {
+            vec![self.statements.len() - 1]
+        } else {
+            self.outputs.clone()
+        }
+    }
+
+    fn input_count(&self) -> usize {
+        self.statements
+            .iter()
+            .map(Expr::input_count)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+struct Table {
+    name: Ident,
+    input_count: Option<(usize, Span)>,
+}
+
+enum Expr {
+    Input(usize, Span),
+    Signal(usize),
+    Const(bool),
+    Gate(Gate, Vec<Expr>),
+}
+
+impl Expr {
+    fn input_count(&self) -> usize {
+        match self {
+            Expr::Input(index, _) => index + 1,
+            Expr::Signal(_) | Expr::Const(_) => 0,
+            Expr::Gate(_, args) => args.iter().map(Expr::input_count).max().unwrap_or(0),
+        }
+    }
+
+    fn check_bounds(&self, input_count: usize) -> ParseResult<()> {
+        match self {
+            Expr::Input(index, span) => {
+                if *index >= input_count {
+                    return Err(CompileError::new(
+                        *span,
+                        format!("Input {index} is out of bounds for a circuit with {input_count} inputs"),
+                    ));
+                }
+                Ok(())
+            }
+            Expr::Signal(_) | Expr::Const(_) => Ok(()),
+            Expr::Gate(_, args) => args
+                .iter()
+                .try_for_each(|arg| arg.check_bounds(input_count)),
+        }
+    }
+
+    /// Evaluates the expression, given the values of all previous statements.
+    fn evaluate(&self, inputs: &[bool], signals: &[bool]) -> bool {
+        match self {
+            Expr::Input(index, _) => inputs[*index],
+            Expr::Signal(signal) => signals[*signal],
+            Expr::Const(value) => *value,
+            Expr::Gate(Gate::Not, args) => !args[0].evaluate(inputs, signals),
+            Expr::Gate(Gate::Buffer, args) => args[0].evaluate(inputs, signals),
+            Expr::Gate(Gate::Or, args) => args.iter().any(|arg| arg.evaluate(inputs, signals)),
+            Expr::Gate(Gate::And, args) => args.iter().all(|arg| arg.evaluate(inputs, signals)),
+            Expr::Gate(Gate::Xor | Gate::Parity, args) => {
+                args.iter()
+                    .filter(|arg| arg.evaluate(inputs, signals))
+                    .count()
+                    % 2
+                    == 1
+            }
+            Expr::Gate(Gate::Majority, args) => {
+                args.iter()
+                    .filter(|arg| arg.evaluate(inputs, signals))
+                    .count()
+                    > args.len() / 2
+            }
+            Expr::Gate(Gate::Threshold(k), args) => {
+                args.iter()
+                    .filter(|arg| arg.evaluate(inputs, signals))
+                    .count()
+                    >= *k
+            }
+            Expr::Gate(Gate::OneHot, args) => {
+                args.iter()
+                    .filter(|arg| arg.evaluate(inputs, signals))
+                    .count()
+                    == 1
+            }
+            Expr::Gate(Gate::Nor, args) => !args.iter().any(|arg| arg.evaluate(inputs, signals)),
+            Expr::Gate(Gate::Nand, args) => !args.iter().all(|arg| arg.evaluate(inputs, signals)),
+            Expr::Gate(Gate::Xnor, args) => {
+                args.iter()
+                    .filter(|arg| arg.evaluate(inputs, signals))
+                    .count()
+                    % 2
+                    == 0
+            }
+        }
+    }
+
+    /// Pushes the statements adding this expression to `__netlist` and returns its node.
+    fn expand(&self, code: &mut Vec<String>) -> String {
+        let node = match self {
+            Expr::Input(index, _) => format!("__netlist.input({index})"),
+            Expr::Signal(signal) => return format!("__signal_{signal}"),
+            Expr::Const(value) => format!("__netlist.constant({value})"),
+            Expr::Gate(Gate::Not, args) => {
+                let input = args[0].expand(code);
+                format!("__netlist.not({input})")
+            }
+            Expr::Gate(Gate::Buffer, args) => return args[0].expand(code),
+            Expr::Gate(Gate::Threshold(k), args) => {
+                let inputs = args.iter().map(|arg| arg.expand(code)).collect::<Vec<_>>();
+                format!("__netlist.threshold({k}, [{}])", inputs.join(", "))
+            }
+            Expr::Gate(gate, args) => {
+                let inputs = args.iter().map(|arg| arg.expand(code)).collect::<Vec<_>>();
+                format!("__netlist.{}([{}])", gate.method(), inputs.join(", "))
+            }
+        };
+        let name = format!("__node_{}", code.len());
+        code.push(format!("let {name} = {node};"));
+        name
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Gate {
+    Not,
+    Buffer,
+    Or,
+    And,
+    /// Two-input exclusive or, where parity and one-hot agree.
+    Xor,
+    Parity,
+    OneHot,
+    Majority,
+    Threshold(usize),
+    Nor,
+    Nand,
+    Xnor,
+}
+
+impl Gate {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "not" => Some(Gate::Not),
+            "buffer" => Some(Gate::Buffer),
+            "or" => Some(Gate::Or),
+            "and" => Some(Gate::And),
+            "xor" => Some(Gate::Xor),
+            "parity" => Some(Gate::Parity),
+            "one_hot" => Some(Gate::OneHot),
+            "majority" => Some(Gate::Majority),
+            "nor" => Some(Gate::Nor),
+            "nand" => Some(Gate::Nand),
+            "xnor" => Some(Gate::Xnor),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Netlist` method adding the gate; buffers have none.
+    fn method(self) -> &'static str {
+        match self {
+            Gate::Not => "not",
+            Gate::Buffer => unreachable!("Buffers are expanded to their argument"),
+            Gate::Or => "or",
+            Gate::And => "and",
+            Gate::Xor | Gate::Parity => "parity",
+            Gate::OneHot => "one_hot",
+            Gate::Majority => "majority",
+            Gate::Threshold(_) => "threshold",
+            Gate::Nor => "nor",
+            Gate::Nand => "nand",
+            Gate::Xnor => "xnor",
+        }
+    }
+
+    fn arity(self) -> &'static str {
+        match self {
+            Gate::Not | Gate::Buffer => "takes exactly one input",
+            Gate::Xor => "takes exactly two inputs",
+            Gate::Threshold(_) => {
+                "requires at least two inputs and a threshold between 1 and its input count"
+            }
+            _ => "requires at least two inputs",
+        }
+    }
+
+    /// Suggests an alternative when the arity does not match.
+    fn hint(self) -> &'static str {
+        match self {
+            Gate::Xor => "; use `parity` or `one_hot` for more",
+            _ => "",
+        }
+    }
+
+    fn accepts(self, arg_count: usize) -> bool {
+        match self {
+            Gate::Not | Gate::Buffer => arg_count == 1,
+            Gate::Xor => arg_count == 2,
+            Gate::Threshold(k) => arg_count > 1 && (1..=arg_count).contains(&k),
+            _ => arg_count > 1,
+        }
+    }
+}
+
+struct CompileError {
+    span: Span,
+    message: String,
+}
+
+impl CompileError {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+
+    fn into_tokens(self) -> TokenStream {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(self.span);
+        let mut args = Group::new(Delimiter::Brace, TokenTree::from(message).into());
+        args.set_span(self.span);
+        TokenStream::from_iter([
+            TokenTree::from(Ident::new("compile_error", self.span)),
+            bang.into(),
+            args.into(),
+        ])
+    }
+}
+
+type ParseResult<T> = Result<T, CompileError>;
+
+fn span_or(token: Option<&TokenTree>, fallback: Span) -> Span {
+    token.map_or(fallback, TokenTree::span)
+}
+
+fn parse_statement(tokens: &mut Peekable<IntoIter>, circuit: &mut Circuit) -> ParseResult<()> {
+    let mut name = match tokens.next() {
+        Some(TokenTree::Ident(name)) => name,
+        token => {
+            return Err(CompileError::new(
+                span_or(token.as_ref(), Span::call_site()),
+                "A statement requires a name",
+            ))
+        }
+    };
+    let output = name.to_string() == "pub";
+    if output {
+        name = match tokens.next() {
+            Some(TokenTree::Ident(name)) => name,
+            token => {
+                return Err(CompileError::new(
+                    span_or(token.as_ref(), name.span()),
+                    "Expected the name of an output after `pub`",
+                ))
+            }
+        };
+    }
+    if !output && name.to_string() == "table" {
+        if let Some(TokenTree::Ident(_)) = tokens.peek() {
+            return parse_table(tokens, circuit, name);
+        }
+    }
+    if !output && name.to_string() == "input" {
+        if let Some(TokenTree::Ident(_)) = tokens.peek() {
+            return parse_inputs(tokens, circuit);
+        }
+    }
+    match tokens.next() {
+        Some(TokenTree::Punct(equals))
+            if equals.as_char() == '=' && equals.spacing() == Spacing::Alone => {}
+        token => {
+            return Err(CompileError::new(
+                span_or(token.as_ref(), name.span()),
+                format!("Expected `=` after `{name}`"),
+            ))
+        }
+    }
+    let expr = parse_expr(tokens, circuit, name.span())?;
+    match tokens.next() {
+        Some(TokenTree::Punct(semicolon)) if semicolon.as_char() == ';' => {}
+        None => {}
+        Some(token) => {
+            return Err(CompileError::new(
+                token.span(),
+                "A statement must be terminated by a semicolon",
+            ))
+        }
+    }
+    if circuit.signals.contains_key(&name.to_string()) {
+        return Err(CompileError::new(
+            name.span(),
+            format!("Signal `{name}` is already defined"),
+        ));
+    }
+    if output {
+        circuit.outputs.push(circuit.statements.len());
+    }
+    circuit
+        .signals
+        .insert(name.to_string(), circuit.statements.len());
+    circuit.names.push(name.to_string());
+    circuit.statements.push(expr);
+    Ok(())
+}
+
+fn parse_inputs(tokens: &mut Peekable<IntoIter>, circuit: &mut Circuit) -> ParseResult<()> {
+    loop {
+        let name = match tokens.next() {
+            Some(TokenTree::Ident(name)) => name,
+            token => {
+                return Err(CompileError::new(
+                    span_or(token.as_ref(), Span::call_site()),
+                    "Expected the name of an input",
+                ))
+            }
+        };
+        if circuit.inputs.contains(&name.to_string()) {
+            return Err(CompileError::new(
+                name.span(),
+                format!("Input `{name}` is already declared"),
+            ));
+        }
+        if circuit.signals.contains_key(&name.to_string()) {
+            return Err(CompileError::new(
+                name.span(),
+                format!("Signal `{name}` is already defined"),
+            ));
+        }
+        let index = circuit.inputs.len();
+        circuit.inputs.push(name.to_string());
+        circuit
+            .signals
+            .insert(name.to_string(), circuit.statements.len());
+        circuit.names.push(name.to_string());
+        circuit.statements.push(Expr::Input(index, name.span()));
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ';' => return Ok(()),
+            None => return Ok(()),
+            Some(token) => {
+                return Err(CompileError::new(
+                    token.span(),
+                    "Input names must be separated by commas",
+                ))
+            }
+        }
+    }
+}
+
+fn parse_table(
+    tokens: &mut Peekable<IntoIter>,
+    circuit: &mut Circuit,
+    keyword: Ident,
+) -> ParseResult<()> {
+    if circuit.table.is_some() {
+        return Err(CompileError::new(
+            keyword.span(),
+            "A circuit can only have one `table` directive",
+        ));
+    }
+    let Some(TokenTree::Ident(name)) = tokens.next() else {
+        unreachable!()
+    };
+    let mut input_count = None;
+    if let Some(TokenTree::Group(group)) = tokens.peek() {
+        let group_span = group.span();
+        let mut args = group.stream().into_iter();
+        let count = match args.next() {
+            Some(TokenTree::Literal(count)) => count,
+            token => {
+                return Err(CompileError::new(
+                    span_or(token.as_ref(), group_span),
+                    "A table requires an input count",
+                ))
+            }
+        };
+        let Ok(value) = count.to_string().parse() else {
+            return Err(CompileError::new(
+                count.span(),
+                "An input count must be an integer",
+            ));
+        };
+        if let Some(token) = args.next() {
+            return Err(CompileError::new(
+                token.span(),
+                "A table takes exactly one input count",
+            ));
+        }
+        input_count = Some((value, count.span()));
+        tokens.next();
+    }
+    match tokens.next() {
+        Some(TokenTree::Punct(semicolon)) if semicolon.as_char() == ';' => {}
+        token => {
+            return Err(CompileError::new(
+                span_or(token.as_ref(), name.span()),
+                "A `table` directive must be terminated by a semicolon",
+            ))
+        }
+    }
+    circuit.table = Some(Table { name, input_count });
+    Ok(())
+}
+
+fn parse_expr(
+    tokens: &mut Peekable<IntoIter>,
+    circuit: &Circuit,
+    previous: Span,
+) -> ParseResult<Expr> {
+    let ident = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident,
+        Some(TokenTree::Literal(literal)) => {
+            return match literal.to_string().as_str() {
+                "0" => Ok(Expr::Const(false)),
+                "1" => Ok(Expr::Const(true)),
+                _ => Err(CompileError::new(
+                    literal.span(),
+                    "A constant must be `0` or `1`",
+                )),
+            }
+        }
+        token => {
+            return Err(CompileError::new(
+                span_or(token.as_ref(), previous),
+                "An expression must be a signal, a gate or a constant",
+            ))
+        }
+    };
+    let name = ident.to_string();
+    let Some(TokenTree::Group(group)) = tokens.peek() else {
+        let Some(signal) = circuit.signals.get(&name) else {
+            return Err(CompileError::new(
+                ident.span(),
+                format!("Undefined signal `{name}`"),
+            ));
+        };
+        return Ok(Expr::Signal(*signal));
+    };
+    if group.delimiter() != Delimiter::Parenthesis {
+        return Err(CompileError::new(
+            group.span(),
+            "Gate arguments must be enclosed in parentheses",
+        ));
+    }
+    let group_span = group.span();
+    let mut args = group.stream().into_iter().peekable();
+    tokens.next();
+    if name == "input" {
+        let index = match args.next() {
+            Some(TokenTree::Literal(index)) => index,
+            token => {
+                return Err(CompileError::new(
+                    span_or(token.as_ref(), group_span),
+                    "An input requires an index",
+                ))
+            }
+        };
+        let Ok(value) = index.to_string().parse() else {
+            return Err(CompileError::new(
+                index.span(),
+                "An input index must be an integer",
+            ));
+        };
+        if let Some(token) = args.next() {
+            return Err(CompileError::new(
+                token.span(),
+                "An input takes exactly one index",
+            ));
+        }
+        return Ok(Expr::Input(value, index.span()));
+    }
+    let gate = if name == "threshold" {
+        Gate::Threshold(parse_threshold(&mut args, group_span)?)
+    } else if let Some(gate) = Gate::from_name(&name) {
+        gate
+    } else {
+        return Err(CompileError::new(
+            ident.span(),
+            format!("Unknown gate `{name}`"),
+        ));
+    };
+    let mut inputs = Vec::new();
+    while args.peek().is_some() {
+        inputs.push(parse_expr(&mut args, circuit, group_span)?);
+        match args.next() {
+            Some(TokenTree::Punct(comma)) if comma.as_char() == ',' => {}
+            None => break,
+            Some(token) => {
+                return Err(CompileError::new(
+                    token.span(),
+                    "Gate arguments must be separated by commas",
+                ))
+            }
+        }
+    }
+    if !gate.accepts(inputs.len()) {
+        return Err(CompileError::new(
+            group_span,
+            format!(
+                "Gate `{name}` {}, but {} were supplied{}",
+                gate.arity(),
+                inputs.len(),
+                gate.hint()
+            ),
+        ));
     }
-    todo!()
+    Ok(Expr::Gate(gate, inputs))
 }
 
-fn parse_statement(tokens: &mut Peekable<IntoIter>) {
-    let TokenTree::Ident(_name) = tokens.next().unwrap() else {
-        panic!("A statement requires a name")
+/// Parses the `k, ` preceding the inputs of a threshold gate.
+fn parse_threshold(args: &mut Peekable<IntoIter>, group_span: Span) -> ParseResult<usize> {
+    let k = match args.next() {
+        Some(TokenTree::Literal(k)) => k,
+        token => {
+            return Err(CompileError::new(
+                span_or(token.as_ref(), group_span),
+                "A threshold gate requires a threshold before its inputs",
+            ))
+        }
     };
-    let Some(TokenTree::Punct(equals)) = tokens.next() else {
-        panic!("A statement requires an equals sign")
+    let Ok(value) = k.to_string().parse() else {
+        return Err(CompileError::new(
+            k.span(),
+            "A threshold must be an integer",
+        ));
     };
-    if equals.as_char() != '=' || equals.spacing() != Spacing::Alone {
-        panic!("A statement requires an equals sign")
+    match args.next() {
+        Some(TokenTree::Punct(comma)) if comma.as_char() == ',' => Ok(value),
+        token => Err(CompileError::new(
+            span_or(token.as_ref(), k.span()),
+            "A threshold must be followed by a comma",
+        )),
     }
 }
@@ -1,6 +1,649 @@
-use emulator::emulator::{and, input, not, or, Emulator};
+use ::emulator::bus::Bus;
+use ::emulator::cpu::AluOp;
+use ::emulator::emulator::{
+    and, buffer, constant, decoder, demux, input, majority, mux, nand, nor, not, one_hot, or,
+    parity, priority_encoder, threshold, xnor, Component, Emulator, Error, Inputs,
+    DEFAULT_ITERATION_LIMIT,
+};
+use ::emulator::logic::Logic;
+use ::emulator::memory::{parse_hex, read_hex_file, Rom};
+use ::emulator::module::{Hierarchy, Module};
+use ::emulator::netlist::Netlist;
+use ::emulator::{emulator, include_circuit};
+
+emulator! {
+    table half_adder(2);
+    a = input(0);
+    b = input(1);
+    pub sum = xor(a, b);
+    pub carry = and(a, b);
+}
 
 fn main() {
-    let emu = Emulator::new(4, and([input(0), or([input(1), input(2)]), not(input(3))])).unwrap();
+    let emu = emulator! {
+        a = input(0);
+        b = input(1);
+        c = input(2);
+        d = input(3);
+        out = and(a, or(b, c), not(d));
+    }
+    .unwrap();
     println!("{}", emu.emulate_all().unwrap());
+    let probability = emu.emulate_values(&[0.5; 4]).unwrap();
+    println!("probability of a random input setting the output: {probability:?}");
+    let majority = include_circuit!("circuits/majority.circuit").unwrap();
+    println!("{}", majority.emulate_all().unwrap());
+    let voter = emulator! {
+        majority = majority(input(0), input(1), input(2));
+    }
+    .unwrap();
+    assert_eq!(
+        voter.emulate_all().unwrap().to_string(),
+        majority.emulate_all().unwrap().to_string()
+    );
+    let full_adder = emulator! {
+        input a, b, cin;
+        partial = xor(a, b);
+        pub sum = xor(partial, cin);
+        pub carry = or(and(a, b), and(partial, cin));
+    }
+    .unwrap();
+    println!("{}", full_adder.emulate_all().unwrap());
+    let outputs = full_adder
+        .emulate_named(&[("cin", true), ("a", true), ("b", false)])
+        .unwrap();
+    println!("a=1 b=0 cin=1 -> {outputs:?}");
+    assert_eq!(outputs, [false, true]);
+    let unknown = full_adder.emulate_named(&[("a", true), ("b", false), ("carry_in", true)]);
+    assert!(matches!(unknown, Err(Error::UnknownInput { name }) if name == "carry_in"));
+    let missing = full_adder.emulate_named(&[("a", true), ("b", false)]);
+    assert!(matches!(missing, Err(Error::MissingInput { name }) if name == "cin"));
+    let duplicate = full_adder.emulate_named(&[("a", true), ("a", false), ("cin", true)]);
+    assert!(matches!(duplicate, Err(Error::DuplicateInput { name }) if name == "a"));
+    let tree = || {
+        or([
+            and([input(0), one_hot([input(1), input(2), input(3)])]),
+            parity([input(1), input(3), input(4)]),
+            not(and([input(2), input(4)])),
+            nor([buffer(input(1)), xnor([input(3), input(4), constant(true)])]),
+            nand([input(0), constant(true), input(2)]),
+        ])
+    };
+    let compiled = Emulator::new(5, tree()).unwrap();
+    let tree: Component = tree();
+    let nand_xor = emulator! {
+        input a, b;
+        both = nand(a, b);
+        pub xor = nand(nand(a, both), nand(b, both));
+        pub tied_low = and(a, 0);
+    }
+    .unwrap();
+    println!("{}", nand_xor.emulate_all().unwrap());
+    let reductions = emulator! {
+        input a, b, c;
+        pub odd = parity(a, b, c);
+        pub exactly_one = one_hot(a, b, c);
+        pub even = xnor(a, b, c);
+    }
+    .unwrap();
+    println!("{}", reductions.emulate_all().unwrap());
+    for row in compiled.rows().unwrap() {
+        let (inputs, outputs) = row.unwrap();
+        assert_eq!(outputs, [tree.emulate(&inputs)]);
+    }
+    let unknown = [Logic::X, Logic::One, Logic::Zero, Logic::Z, Logic::One];
+    assert_eq!(
+        compiled.emulate_logic(&unknown).unwrap(),
+        [tree.emulate(&unknown)]
+    );
+    let wide = Emulator::new(40, and((32..40).map(input))).unwrap();
+    let first_true = wide.rows().unwrap().position(|row| row.unwrap().1[0]);
+    println!("first true row of a 40-input circuit: {first_true:?}");
+    let mut counter = counter();
+    let cycles = [
+        [true, false],
+        [true, false],
+        [false, false],
+        [true, false],
+        [true, true],
+    ];
+    let trace = counter.simulate(cycles).unwrap();
+    println!("{trace}");
+    let counts = [
+        [false, false],
+        [false, true],
+        [true, false],
+        [true, false],
+        [true, true],
+    ];
+    for (cycle, count) in counts.iter().enumerate() {
+        assert_eq!(trace.outputs(cycle), count);
+    }
+    // The last cycle resets the counter.
+    assert_eq!(counter.state(), [false, false]);
+    let reset_sequence = [
+        [Logic::Zero, Logic::One],
+        [Logic::One, Logic::Zero],
+        [Logic::X, Logic::Zero],
+    ];
+    let trace = counter.simulate_logic(reset_sequence).unwrap();
+    println!("{trace}");
+    // The state is unknown until the first clock edge resets it.
+    assert_eq!(trace.outputs(0), [Logic::X, Logic::X]);
+    assert_eq!(trace.outputs(1), [Logic::Zero, Logic::Zero]);
+    assert_eq!(trace.outputs(2), [Logic::Zero, Logic::One]);
+    assert_eq!(Logic::Zero & Logic::X, Logic::Zero);
+    assert_eq!(Logic::One & Logic::X, Logic::X);
+    assert_eq!(Logic::Zero & Logic::Z, Logic::Zero);
+    assert_eq!(Logic::One & Logic::Z, Logic::X);
+    assert_eq!(Logic::One | Logic::Z, Logic::One);
+    assert_eq!(!Logic::Z, Logic::X);
+    let gate = Emulator::new(2, and([input(0), input(1)])).unwrap();
+    let table = gate.emulate_all_logic().unwrap();
+    println!("{table}");
+    for row in 0..table.row_count() {
+        let [a, b] = table.inputs(row)[..] else {
+            unreachable!()
+        };
+        assert_eq!(table.outputs(row), [a & b]);
+    }
+    check_threads();
+    check_flip_flops();
+    check_selectors();
+    check_thresholds();
+    check_buses();
+    check_modules();
+    for width in 1..=4 {
+        check_arithmetic(width);
+    }
+    check_alu();
+    check_memories();
+    let mut latch = nor_latch();
+    let cycles = [[true, false], [false, false], [false, true], [false, false]];
+    let trace = latch.simulate(cycles).unwrap();
+    println!("{trace}");
+    for (cycle, q) in [true, true, false, false].into_iter().enumerate() {
+        assert_eq!(trace.outputs(cycle), [q]);
+    }
+    let mut ring = Netlist::new();
+    let wire = ring.wire();
+    let inverted = ring.not(wire);
+    ring.drive(wire, inverted);
+    let ring = Emulator::from_netlist(0, ring, [("out", inverted)]);
+    assert!(matches!(ring, Err(Error::CombinationalLoop { .. })));
+    let mut oscillator = Netlist::new();
+    let wire = oscillator.feedback_wire();
+    let inverted = oscillator.not(wire);
+    oscillator.drive(wire, inverted);
+    let mut oscillator = Emulator::from_netlist(0, oscillator, [("out", inverted)]).unwrap();
+    let unstable = oscillator.emulate(&[]);
+    assert!(matches!(
+        unstable,
+        Err(Error::Unstable { iterations, .. }) if iterations == DEFAULT_ITERATION_LIMIT
+    ));
+    oscillator.set_iteration_limit(5);
+    let unstable = oscillator.emulate(&[]);
+    assert!(matches!(
+        unstable,
+        Err(Error::Unstable { iterations: 5, .. })
+    ));
+    let no_outputs = Emulator::with_outputs(2, Vec::<(&str, Component)>::new());
+    assert!(matches!(no_outputs, Err(Error::NoOutputs)));
+    // 32 inputs are allowed, but not with a table bit for each of 8 outputs.
+    let wide = Emulator::with_outputs(32, (0..8).map(|i| (format!("o{i}"), input(i))));
+    let table = wide.unwrap().emulate_all();
+    assert!(matches!(table, Err(Error::TableTooLarge { .. })));
+    let runtime_half_adder = emulator! {
+        a = input(0);
+        b = input(1);
+        pub sum = xor(a, b);
+        pub carry = and(a, b);
+    }
+    .unwrap();
+    for inputs in [[false, false], [false, true], [true, false], [true, true]] {
+        let outputs = half_adder(inputs);
+        println!("{inputs:?} -> {outputs:?}");
+        assert_eq!(outputs[..], runtime_half_adder.emulate(&inputs).unwrap());
+    }
+}
+
+/// A 2-bit counter counting up while `enable` is high.
+fn counter() -> Emulator {
+    let mut netlist = Netlist::new();
+    let enable = netlist.input(0);
+    let reset = netlist.input(1);
+    let low = netlist.state();
+    let high = netlist.state();
+    let next_low = netlist.not(low);
+    let next_high = netlist.parity([high, low]);
+    netlist.register(low, next_low, enable, reset);
+    netlist.register(high, next_high, enable, reset);
+    let inputs = Inputs::new(["enable", "reset"]).unwrap();
+    Emulator::from_netlist(inputs, netlist, [("q1", high), ("q0", low)]).unwrap()
+}
+
+/// Checks a D flip-flop, an SR latch and a JK flip-flop clocked side by side.
+fn check_flip_flops() {
+    let mut netlist = Netlist::new();
+    let [d, s, r, j, k] = [0, 1, 2, 3, 4].map(|index| netlist.input(index));
+    let [q_d, q_sr, q_jk] = [(); 3].map(|_| netlist.state());
+    netlist.d_flip_flop(q_d, d);
+    netlist.sr_latch(q_sr, s, r);
+    netlist.jk_flip_flop(q_jk, j, k);
+    let inputs = Inputs::new(["d", "s", "r", "j", "k"]).unwrap();
+    let outputs = [("q_d", q_d), ("q_sr", q_sr), ("q_jk", q_jk)];
+    let mut emulator = Emulator::from_netlist(inputs, netlist, outputs).unwrap();
+    let cycles = [
+        // Set wins over reset, and J and K together toggle.
+        ([1, 1, 1, 1, 1], [0, 0, 0]),
+        ([0, 0, 1, 1, 1], [1, 1, 1]),
+        ([0, 0, 0, 1, 0], [0, 0, 0]),
+        ([1, 0, 0, 0, 0], [0, 0, 1]),
+        ([0, 0, 0, 0, 1], [1, 0, 1]),
+        ([0, 0, 0, 0, 0], [0, 0, 0]),
+    ];
+    for (inputs, expected) in cycles {
+        let outputs = emulator.step(&inputs.map(|bit| bit == 1)).unwrap();
+        assert_eq!(outputs, expected.map(|bit| bit == 1));
+    }
+}
+
+/// A set/reset latch made of two cross-coupled NOR gates.
+fn nor_latch() -> Emulator {
+    let mut netlist = Netlist::new();
+    let set = netlist.input(0);
+    let reset = netlist.input(1);
+    let q_bar = netlist.feedback_wire();
+    let reset_or_q_bar = netlist.or([reset, q_bar]);
+    let q = netlist.not(reset_or_q_bar);
+    let set_or_q = netlist.or([set, q]);
+    let next_q_bar = netlist.not(set_or_q);
+    netlist.drive(q_bar, next_q_bar);
+    let inputs = Inputs::new(["s", "r"]).unwrap();
+    Emulator::from_netlist(inputs, netlist, [("q", q)]).unwrap()
+}
+
+/// Checks that splitting a table across threads gives the same rows as emulating them one by one,
+/// with enough rows for several chunks and three outputs per row so chunks start mid-word.
+fn check_threads() {
+    let outputs = [
+        ("odd", parity((0..17).map(input))),
+        ("most", majority((0..9).map(input))),
+        (
+            "edge",
+            and([input(0), not(input(16)), or([input(5), input(11)])]),
+        ),
+    ];
+    let emulator = Emulator::with_outputs(17, outputs).unwrap();
+    let rows = emulator
+        .rows()
+        .unwrap()
+        .map(|row| row.unwrap().1)
+        .collect::<Vec<_>>();
+    for threads in [1, 3, 8] {
+        let table = emulator.emulate_all_with_threads(threads).unwrap();
+        assert_eq!(table.row_count(), rows.len());
+        for (row, outputs) in rows.iter().enumerate() {
+            assert_eq!(
+                &table.outputs(row),
+                outputs,
+                "row {row} with {threads} threads"
+            );
+        }
+    }
+}
+
+/// Checks multiplexers, decoders and priority encoders against their definitions, both compiled
+/// and as component trees.
+fn check_selectors() {
+    let check =
+        |components: Vec<Component>, input_count: usize, expected: &dyn Fn(usize) -> Vec<bool>| {
+            let outputs = components
+                .iter()
+                .enumerate()
+                .map(|(i, component)| (format!("O{i}"), component.clone()));
+            let emulator = Emulator::with_outputs(input_count, outputs).unwrap();
+            let table = emulator.emulate_all().unwrap();
+            for row in 0..table.row_count() {
+                let inputs = (0..input_count)
+                    .map(|i| row >> (input_count - 1 - i) & 1 != 0)
+                    .collect::<Vec<_>>();
+                let trees = components
+                    .iter()
+                    .map(|component| component.emulate(&inputs))
+                    .collect::<Vec<bool>>();
+                assert_eq!(table.outputs(row), expected(row));
+                assert_eq!(trees, expected(row));
+            }
+        };
+    let select = || [input(0), input(1)];
+    check(vec![mux(select(), (2..6).map(input))], 6, &|row| {
+        vec![row >> (3 - (row >> 4)) & 1 != 0]
+    });
+    check(demux(select(), input(2)), 3, &|row| {
+        (0..4).map(|i| row & 1 != 0 && row >> 1 == i).collect()
+    });
+    check(decoder((0..3).map(input)), 3, &|row| {
+        (0..8).map(|i| row == i).collect()
+    });
+    check(priority_encoder((0..5).map(input)), 5, &|row| {
+        // Input 4 is the least significant bit of the row.
+        let last = (0..5).rev().find(|i| row >> (4 - i) & 1 != 0);
+        let index = last.unwrap_or(0);
+        vec![
+            index & 4 != 0,
+            index & 2 != 0,
+            index & 1 != 0,
+            last.is_some(),
+        ]
+    });
+    let encoder = ["a1", "a0", "valid"]
+        .into_iter()
+        .zip(priority_encoder((0..4).map(input)));
+    let encoder = Emulator::with_outputs(4, encoder).unwrap();
+    println!("{}", encoder.emulate_all().unwrap());
+}
+
+/// Checks every threshold gate with two to nine inputs against a population count.
+fn check_thresholds() {
+    for n in 2..=9 {
+        for k in 1..=n {
+            let gate = threshold(k, (0..n).map(input));
+            let emulator = Emulator::new(n, gate.clone()).unwrap();
+            for row in emulator.rows().unwrap() {
+                let (inputs, outputs) = row.unwrap();
+                let expected = inputs.iter().filter(|input| **input).count() >= k;
+                assert_eq!(outputs, [expected]);
+                assert_eq!(gate.emulate(&inputs), expected);
+            }
+            let table = emulator.emulate_all().unwrap();
+            for row in 0..table.row_count() {
+                assert_eq!(table.output(row, 0), row.count_ones() as usize >= k);
+            }
+        }
+    }
+    let tmr = majority((0..3).map(input));
+    let failure = tmr.emulate(&[0.1; 3]);
+    println!("probability of a triplicated module failing at 10% per copy: {failure:.3}");
+}
+
+/// Checks bitwise bus operations, slicing, concatenation and replication against Rust integers.
+fn check_buses() {
+    let mut netlist = Netlist::new();
+    let a = netlist.input_bus(0, 4);
+    let b = netlist.input_bus(4, 4);
+    let select = netlist.input(8);
+    let outputs = [
+        ("and", netlist.bus_and(&a, &b)),
+        ("or", netlist.bus_or(&a, &b)),
+        ("xor", netlist.bus_xor(&a, &b)),
+        ("not", netlist.bus_not(&a)),
+        ("mux", netlist.bus_mux(&[select], &[a.clone(), b.clone()])),
+        ("swap", Bus::concat([a.slice(..2), b.slice(2..)])),
+        ("spread", Bus::from(select).replicate(3)),
+    ];
+    let inputs = [("a", a), ("b", b), ("s", Bus::from(select))];
+    let emulator = Emulator::from_buses(inputs, netlist, outputs).unwrap();
+    for a in 0..16 {
+        for b in 0..16 {
+            for s in 0..2 {
+                let expected = [
+                    a & b,
+                    a | b,
+                    a ^ b,
+                    !a & 15,
+                    if s == 0 { a } else { b },
+                    (a & 3) << 2 | b >> 2,
+                    s * 7,
+                ];
+                assert_eq!(emulator.emulate_buses(&[a, b, s]).unwrap(), expected);
+            }
+        }
+    }
+}
+
+fn half_adder_module() -> Module {
+    let mut module = Module::new("half_adder", [("a", 1), ("b", 1)]).unwrap();
+    let a = module.input("a").unwrap().bit(0);
+    let b = module.input("b").unwrap().bit(0);
+    let netlist = module.netlist_mut();
+    let sum = netlist.parity([a, b]);
+    let carry = netlist.and([a, b]);
+    module.set_output("sum", sum).unwrap();
+    module.set_output("carry", carry).unwrap();
+    module
+}
+
+fn full_adder_module() -> Module {
+    let half_adder = half_adder_module();
+    let mut module = Module::new("full_adder", [("a", 1), ("b", 1), ("cin", 1)]).unwrap();
+    let [a, b, cin] = ["a", "b", "cin"].map(|name| module.input(name).unwrap().clone());
+    let netlist = module.netlist_mut();
+    let ha1 = netlist
+        .instantiate(&half_adder, "ha1", [("a", a), ("b", b)])
+        .unwrap();
+    let ha1_sum = ha1.output("sum").unwrap().clone();
+    let ha2 = netlist
+        .instantiate(&half_adder, "ha2", [("a", ha1_sum), ("b", cin)])
+        .unwrap();
+    let carries = [ha1.output("carry").unwrap(), ha2.output("carry").unwrap()];
+    let cout = netlist.or(carries.map(|carry| carry.bit(0)));
+    module
+        .set_output("sum", ha2.output("sum").unwrap().clone())
+        .unwrap();
+    module.set_output("cout", cout).unwrap();
+    module
+}
+
+fn check_modules() {
+    let full_adder = full_adder_module();
+    let mut module = Module::new("adder4", [("a", 4), ("b", 4), ("cin", 1)]).unwrap();
+    let a = module.input("a").unwrap().clone();
+    let b = module.input("b").unwrap().clone();
+    let mut carry = module.input("cin").unwrap().clone();
+    let mut sum = Vec::new();
+    for bit in 0..4 {
+        let inputs = [
+            ("a", a.slice(bit..=bit)),
+            ("b", b.slice(bit..=bit)),
+            ("cin", carry),
+        ];
+        let instance = module
+            .netlist_mut()
+            .instantiate(&full_adder, &format!("fa{bit}"), inputs)
+            .unwrap();
+        sum.push(instance.output("sum").unwrap().bit(0));
+        carry = instance.output("cout").unwrap().clone();
+    }
+    module.set_output("sum", Bus::new(sum)).unwrap();
+    module.set_output("cout", carry.clone()).unwrap();
+    let clash = module.set_output("cin", carry);
+    assert!(matches!(clash, Err(Error::DuplicateSignal { .. })));
+    let netlist = module.netlist_mut();
+    netlist.set_name(a.bit(0), "a[0]").unwrap();
+    let renamed = netlist.set_name(a.bit(1), "a[0]");
+    assert!(matches!(renamed, Err(Error::DuplicateSignal { .. })));
+    assert_eq!(netlist.named("a[0]"), Some(a.bit(0)));
+    let overlap = Module::new("overlap", [("a", 2), ("a[1]", 1)]);
+    assert!(matches!(overlap, Err(Error::DuplicateSignal { .. })));
+    let size = module.netlist().len();
+    let inputs = [
+        ("a", a.slice(..1)),
+        ("b", b.slice(..1)),
+        ("cin", b.slice(1..2)),
+    ];
+    let again = module.netlist_mut().instantiate(&full_adder, "fa0", inputs);
+    assert!(matches!(again, Err(Error::DuplicateInstance { .. })));
+    assert_eq!(module.netlist().len(), size);
+    let emulator = Emulator::from_module(&module, Hierarchy::Keep).unwrap();
+    let flat = Emulator::from_module(&module, Hierarchy::Flatten).unwrap();
+    for a in 0..16 {
+        for b in 0..16 {
+            for cin in 0..2 {
+                let total = a + b + cin;
+                let expected = [total & 15, total >> 4];
+                assert_eq!(emulator.emulate_buses(&[a, b, cin]).unwrap(), expected);
+                assert_eq!(flat.emulate_buses(&[a, b, cin]).unwrap(), expected);
+            }
+        }
+    }
+    // Inputs are a[3..0], b[3..0], cin, so this adds 0b0100 and 0b0100.
+    let inputs = [0, 1, 0, 0, 0, 1, 0, 0, 0].map(|bit| bit == 1);
+    assert!(emulator.probe(&inputs, "fa2.ha1.carry").unwrap());
+    assert!(!emulator.probe(&inputs, "fa2.sum").unwrap());
+    assert!(emulator.probe(&inputs, "fa3.cin").unwrap());
+    assert!(flat.probe(&inputs, "fa2.ha1.carry").is_err());
+    println!(
+        "adder4 keeps {} named signals, e.g. {:?}",
+        emulator.probe_names().count(),
+        emulator
+            .probe_names()
+            .find(|name| name.starts_with("fa1.ha2.")),
+    );
+}
+
+/// Checks every arithmetic generator against Rust integer arithmetic for all operands of `width`
+/// bits.
+fn check_arithmetic(width: usize) {
+    let mut netlist = Netlist::new();
+    let a = netlist.input_bus(0, width);
+    let b = netlist.input_bus(width, width);
+    let carry = netlist.input(2 * width);
+    let join = |(sum, carry): (Bus, _)| Bus::concat([Bus::from(carry), sum]);
+    let outputs = [
+        ("ripple", join(netlist.ripple_carry_adder(&a, &b, carry))),
+        (
+            "lookahead",
+            join(netlist.carry_lookahead_adder(&a, &b, carry)),
+        ),
+        (
+            "kogge_stone",
+            join(netlist.kogge_stone_adder(&a, &b, carry)),
+        ),
+        ("difference", join(netlist.subtractor(&a, &b))),
+        ("increment", join(netlist.incrementer(&a))),
+        ("eq", Bus::from(netlist.equal(&a, &b))),
+        ("lt", Bus::from(netlist.less_than(&a, &b))),
+        ("gt", Bus::from(netlist.greater_than(&a, &b))),
+        ("product", netlist.array_multiplier(&a, &b)),
+    ];
+    let inputs = [("a", a), ("b", b), ("c", Bus::from(carry))];
+    let emulator = Emulator::from_buses(inputs, netlist, outputs).unwrap();
+    let limit = 1u64 << width;
+    for a in 0..limit {
+        for b in 0..limit {
+            for c in 0..2 {
+                let sum = a + b + c;
+                // The borrow lands in the carry position, like a two's complement wraparound.
+                let difference = a.wrapping_sub(b) & (2 * limit - 1);
+                let expected = [
+                    sum,
+                    sum,
+                    sum,
+                    difference,
+                    a + 1,
+                    (a == b) as u64,
+                    (a < b) as u64,
+                    (a > b) as u64,
+                    a * b,
+                ];
+                assert_eq!(emulator.emulate_buses(&[a, b, c]).unwrap(), expected);
+            }
+        }
+    }
+}
+
+fn check_alu() {
+    let mut netlist = Netlist::new();
+    let op = netlist.input_bus(0, 3);
+    let a = netlist.input_bus(3, 4);
+    let b = netlist.input_bus(7, 4);
+    let alu = netlist.alu(&AluOp::ALL, &op, &a, &b);
+    let flags = [alu.zero, alu.negative, alu.carry, alu.overflow];
+    let outputs = [("result", alu.result), ("flags", Bus::new(flags))];
+    let inputs = [("op", op), ("a", a), ("b", b)];
+    let emulator = Emulator::from_buses(inputs, netlist, outputs).unwrap();
+    let signed = |value: u64| (value as i64) << 60 >> 60;
+    for (code, operation) in AluOp::ALL.iter().enumerate() {
+        for a in 0..16 {
+            for b in 0..16 {
+                let (result, carry, overflow) = match operation {
+                    AluOp::Add => (
+                        a + b,
+                        a + b > 15,
+                        !(-8..8).contains(&(signed(a) + signed(b))),
+                    ),
+                    AluOp::Sub => (
+                        a.wrapping_sub(b),
+                        a < b,
+                        !(-8..8).contains(&(signed(a) - signed(b))),
+                    ),
+                    AluOp::And => (a & b, false, false),
+                    AluOp::Or => (a | b, false, false),
+                    AluOp::Xor => (a ^ b, false, false),
+                    AluOp::ShiftLeft => (a << (b & 3), false, false),
+                    AluOp::ShiftRight => (a >> (b & 3), false, false),
+                };
+                let result = result & 15;
+                let flags = [result == 0, result >= 8, carry, overflow]
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, flag)| u64::from(*flag) << bit)
+                    .sum();
+                let outputs = emulator.emulate_buses(&[code as u64, a, b]).unwrap();
+                assert_eq!(outputs, [result, flags], "{operation:?} {a} {b}");
+            }
+        }
+    }
+    // The unused opcode gives zero.
+    assert_eq!(emulator.emulate_buses(&[7, 5, 3]).unwrap(), [0, 1]);
+}
+
+fn check_memories() {
+    let directory = std::env::temp_dir();
+    let squares = (0..16u8).map(|n| n * n).collect::<Vec<_>>();
+    let binary = directory.join("emulator-squares.bin");
+    std::fs::write(&binary, &squares).unwrap();
+    let rom = Rom::from_binary_file(&binary).unwrap();
+    let hex = directory.join("emulator-ram.hex");
+    std::fs::write(&hex, "// initial contents\n7 0a\nff\n").unwrap();
+    let contents = read_hex_file(&hex).unwrap();
+    assert_eq!(contents, [7, 10, 255]);
+    let sparse = parse_hex("@2 1_0 @0 5\n@6 f_f // last word\n").unwrap();
+    assert_eq!(sparse, [5, 0, 16, 0, 0, 0, 255]);
+    let invalid = parse_hex("1\n@g 2\n");
+    assert!(matches!(invalid, Err(Error::InvalidHex { line: 2, .. })));
+    let distant = parse_hex("@10000 1");
+    assert!(matches!(distant, Err(Error::AddressOutOfRange { .. })));
+
+    let mut netlist = Netlist::new();
+    let address = netlist.input_bus(0, 4);
+    let data = netlist.input_bus(4, 8);
+    let write = netlist.input(12);
+    let square = netlist.rom(&rom, &address).unwrap();
+    let narrow = address.slice(..3);
+    assert!(netlist.rom(&rom, &narrow).is_err());
+    assert!(matches!(Rom::new(70, [1]), Err(Error::BusTooWide { .. })));
+    let wide = Bus::from(write).replicate(65);
+    let wide_ram = netlist.ram(&address, &wide, write);
+    assert!(matches!(wide_ram, Err(Error::BusTooWide { .. })));
+    let ram = netlist.ram(&address, &data, write).unwrap();
+    let inputs = [
+        ("address", address),
+        ("data", data),
+        ("write", Bus::from(write)),
+    ];
+    let outputs = [("square", square), ("read", ram.read().clone())];
+    let mut emulator = Emulator::from_buses(inputs, netlist, outputs).unwrap();
+    emulator.load_ram(&ram, &contents).unwrap();
+    assert!(emulator.load_ram(&ram, &[0; 17]).is_err());
+    for address in 0..16 {
+        let expected_ram = contents.get(address as usize).copied().unwrap_or(0);
+        let outputs = emulator.emulate_buses(&[address, 0, 0]).unwrap();
+        assert_eq!(outputs, [address * address, expected_ram]);
+    }
+    // Writes take effect on the clock edge, so the old word is read during the write.
+    assert_eq!(emulator.step_buses(&[1, 42, 1]).unwrap()[1], 10);
+    assert_eq!(emulator.step_buses(&[1, 99, 0]).unwrap()[1], 42);
+    assert_eq!(emulator.ram_contents(&ram)[..3], [7, 42, 255]);
 }